@@ -0,0 +1,34 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// The extensions of the files to compress, the search index being a JavaScript or JSON file
+pub const COMPRESSED_EXTENSIONS: [&str; 6] = ["html", "xml", "css", "js", "json", "svg"];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Compression {
+    /// Whether to write a compressed copy of the text files of the output directory next to them,
+    /// eg `index.html.gz` and `index.html.br` for `index.html`
+    pub enabled: bool,
+    /// Whether to write the gzip copies
+    pub gzip: bool,
+    /// Whether to write the brotli copies
+    pub brotli: bool,
+    /// Files smaller than that number of bytes are not compressed
+    pub min_size: usize,
+}
+
+impl Compression {
+    /// Whether a file with the given extension and size should be compressed
+    pub fn should_compress(&self, extension: &str, size: usize) -> bool {
+        self.enabled
+            && (self.gzip || self.brotli)
+            && size >= self.min_size
+            && COMPRESSED_EXTENSIONS.contains(&extension)
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression { enabled: false, gzip: true, brotli: true, min_size: 1024 }
+    }
+}
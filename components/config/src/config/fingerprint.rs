@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde_derive::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fingerprint {
+    /// Whether to add the hash of their content to the names of the static files and of the
+    /// Sass output matching `files`, eg `css/site.css` becoming `css/site.<hash>.css`
+    pub enabled: bool,
+    /// Glob patterns of the files to fingerprint, relative to the `static` directory
+    pub files: Vec<String>,
+    /// The fingerprinted path of every matching file, keyed by its original path.
+    /// Filled when loading the site, it stays empty in `zola serve`
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub manifest: HashMap<String, String>,
+}
+
+impl Fingerprint {
+    pub fn globset(&self) -> Result<GlobSet> {
+        let mut glob_set_builder = GlobSetBuilder::new();
+        for pat in &self.files {
+            let glob = match Glob::new(pat) {
+                Ok(g) => g,
+                Err(e) => bail!("Invalid fingerprint.files glob pattern: {}, error = {}", pat, e),
+            };
+            glob_set_builder.add(glob);
+        }
+        Ok(glob_set_builder.build().expect("Bad fingerprint.files in config file."))
+    }
+
+    /// Returns the link to the fingerprinted file if the site-relative `link` points to a
+    /// fingerprinted asset, keeping its query string and fragment
+    pub fn fingerprint_link(&self, link: &str) -> Option<String> {
+        let path = link.strip_prefix('/')?;
+        let end = path.find(&['?', '#'][..]).unwrap_or(path.len());
+        let fingerprinted = self.manifest.get(&path[..end])?;
+        Some(format!("/{}{}", fingerprinted, &path[end..]))
+    }
+}
+
+impl Default for Fingerprint {
+    fn default() -> Fingerprint {
+        Fingerprint {
+            enabled: false,
+            files: vec!["**/*.css".to_string(), "**/*.js".to_string()],
+            manifest: HashMap::new(),
+        }
+    }
+}
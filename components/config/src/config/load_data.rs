@@ -0,0 +1,29 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadData {
+    /// Whether to keep the responses of the remote `load_data` calls on disk between builds
+    pub cache: bool,
+    /// Where to keep the responses, relative to the site directory
+    pub cache_dir: String,
+    /// For how many seconds a response is used before requesting the URL again
+    pub cache_ttl: u64,
+    /// A directory of files used instead of requesting the URLs, relative to the site directory
+    pub fixtures_dir: Option<String>,
+    /// Whether to never request URLs, set with `--offline`
+    #[serde(skip)]
+    pub offline: bool,
+}
+
+impl Default for LoadData {
+    fn default() -> LoadData {
+        LoadData {
+            cache: false,
+            cache_dir: ".zola-cache".to_string(),
+            cache_ttl: 24 * 60 * 60,
+            fixtures_dir: None,
+            offline: false,
+        }
+    }
+}
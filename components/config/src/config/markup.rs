@@ -1,4 +1,4 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use serde_derive::{Deserialize, Serialize};
 use syntect::{
@@ -28,6 +28,107 @@ impl Default for ThemeCss {
     }
 }
 
+/// When to use the dark theme of `highlight_theme = { light = "...", dark = "..." }`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DarkMode {
+    /// Follows the `prefers-color-scheme` media query
+    Media,
+    /// Only when the root element has the `dark` class
+    Class,
+    /// Follows the media query unless the root element has a `light` or `dark` class
+    Both,
+}
+
+fn default_light_dark_filename() -> String {
+    "syntax-theme.css".to_owned()
+}
+
+fn default_dark_mode() -> DarkMode {
+    DarkMode::Both
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightDarkThemes {
+    pub light: String,
+    pub dark: String,
+    /// In which file, relative to the `static` directory, are we going to output the CSS
+    #[serde(default = "default_light_dark_filename")]
+    pub filename: String,
+    #[serde(default = "default_dark_mode")]
+    pub dark_mode: DarkMode,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HighlightTheme {
+    /// A theme used inline, or `css` to only output classes
+    Single(String),
+    /// Two themes, code is highlighted with classes and their CSS exported in a single stylesheet
+    LightDark(LightDarkThemes),
+}
+
+/// Prefixes every selector of a stylesheet generated by syntect with the given scope
+fn scope_theme_css(css: &str, scope: &str) -> String {
+    let mut res = String::with_capacity(css.len() * 2);
+    for line in css.lines() {
+        match line.strip_suffix(" {") {
+            Some(selectors) if !line.starts_with(' ') => {
+                let scoped: Vec<_> =
+                    selectors.split(", ").map(|s| format!("{} {}", scope, s)).collect();
+                res.push_str(&scoped.join(", "));
+                res.push_str(" {");
+            }
+            _ => res.push_str(line),
+        }
+        res.push('\n');
+    }
+    res
+}
+
+/// The library highlighting code blocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HighlightBackend {
+    Syntect,
+    /// Only for the languages with a bundled grammar, the others fall back to syntect
+    TreeSitter,
+}
+
+/// A command rendering the code blocks of a language, e.g. `dot -Tsvg` for `dot` blocks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FenceProcessor {
+    /// The command receives the code block on stdin and its stdout is inlined in the page
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponsiveImages {
+    /// The widths (in pixels) of the resized images put in the `srcset`
+    pub widths: Vec<u32>,
+    /// The formats the images are resized to, one `<source>` per format.
+    /// `auto` keeps JPEG for lossy images and PNG for lossless ones
+    pub formats: Vec<String>,
+    /// The value of the `sizes` attribute
+    pub sizes: String,
+    /// The quality of the lossy formats, between 1 and 100
+    pub quality: Option<u8>,
+}
+
+impl Default for ResponsiveImages {
+    fn default() -> ResponsiveImages {
+        ResponsiveImages {
+            widths: Vec::new(),
+            formats: vec!["auto".to_owned()],
+            sizes: "100vw".to_owned(),
+            quality: None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SectionTagsMode {
@@ -42,11 +143,26 @@ pub struct Markdown {
     pub highlight_code: bool,
     /// Which themes to use for code highlighting. See Readme for supported themes
     /// Defaults to "base16-ocean-dark"
-    pub highlight_theme: String,
+    pub highlight_theme: HighlightTheme,
     /// Generate CSS files for Themes out of syntect
     pub highlight_themes_css: Vec<ThemeCss>,
+    /// Which library highlights the code blocks. Defaults to syntect
+    pub highlighter: HighlightBackend,
+    /// Overrides `highlighter` for the given languages
+    pub language_highlighters: HashMap<String, HighlightBackend>,
+    /// The commands rendering the code blocks of the given languages instead of highlighting them
+    pub fence_processors: HashMap<String, FenceProcessor>,
+    /// Where to keep the output of the `fence_processors` between builds, relative to the site
+    /// directory. The commands run for every build if not set
+    pub fence_processors_cache_dir: Option<String>,
+    /// Whether to give an `id` to every line of the code blocks with line numbers
+    pub code_line_anchors: bool,
     /// Whether to render emoji aliases (e.g.: :smile: => 😄) in the markdown files
     pub render_emoji: bool,
+    /// Whether to render GitHub-style admonitions (`> [!NOTE]`) with the `admonition.html` template
+    pub render_admonitions: bool,
+    /// Whether to resolve `[[Page title]]` wiki links to the page with that slug or title
+    pub wiki_links: bool,
     /// Whether external links are to be opened in a new tab
     /// If this is true, a `rel="noopener"` will always automatically be added for security reasons
     pub external_links_target_blank: bool,
@@ -58,6 +174,13 @@ pub struct Markdown {
     pub render_with_section_tags: Option<SectionTagsMode>,
     /// Whether smart punctuation is enabled (changing quotes, dashes, dots etc in their typographic form)
     pub smart_punctuation: bool,
+    /// The minimum heading level included in the table of contents. Defaults to 1
+    pub toc_min_level: u32,
+    /// The maximum heading level included in the table of contents. Defaults to 6
+    pub toc_max_level: u32,
+    /// If set, local images are resized to the given widths and rendered with the
+    /// `responsive-image.html` template
+    pub responsive_images: Option<ResponsiveImages>,
     /// A list of directories to search for additional `.sublime-syntax` and `.tmTheme` files in.
     pub extra_syntaxes_and_themes: Vec<String>,
     /// The compiled extra syntaxes into a syntax set
@@ -69,13 +192,20 @@ pub struct Markdown {
 }
 
 impl Markdown {
+    /// The backend highlighting the code blocks of a language
+    pub fn language_highlighter(&self, lang: &str) -> HighlightBackend {
+        self.language_highlighters.get(lang).copied().unwrap_or(self.highlighter)
+    }
+
     /// Gets the configured highlight theme from the THEME_SET or the config's extra_theme_set
     /// Returns None if the configured highlighting theme is set to use css
+    /// Light/dark themes are also highlighted with classes
     pub fn get_highlight_theme(&self) -> Option<&Theme> {
-        if self.highlight_theme == "css" {
-            None
-        } else {
-            Some(self.get_highlight_theme_by_name(&self.highlight_theme))
+        match self.highlight_theme {
+            HighlightTheme::Single(ref name) if name != "css" => {
+                Some(self.get_highlight_theme_by_name(name))
+            }
+            _ => None,
         }
     }
 
@@ -115,10 +245,40 @@ impl Markdown {
         css_for_theme_with_class_style(theme, CLASS_STYLE)
     }
 
-    pub fn init_extra_syntaxes_and_highlight_themes(&mut self, path: &Path) -> Result<()> {
-        if self.highlight_theme == "css" {
-            return Ok(());
+    /// Exports the CSS of both light and dark themes in a single stylesheet, each scoped
+    /// so that only one of them applies at a time
+    pub fn export_light_dark_theme_css(&self, themes: &LightDarkThemes) -> String {
+        let light = self.export_theme_css(&themes.light);
+        let dark = self.export_theme_css(&themes.dark);
+
+        match themes.dark_mode {
+            DarkMode::Media => format!(
+                "@media (prefers-color-scheme: light) {{\n{}}}\n\n@media (prefers-color-scheme: dark) {{\n{}}}\n",
+                light, dark
+            ),
+            DarkMode::Class => format!(
+                "{}\n{}",
+                scope_theme_css(&light, ":root:not(.dark)"),
+                scope_theme_css(&dark, ":root.dark")
+            ),
+            DarkMode::Both => format!(
+                "@media (prefers-color-scheme: light) {{\n{}}}\n\n{}\n@media (prefers-color-scheme: dark) {{\n{}}}\n\n{}",
+                scope_theme_css(&light, ":root:not(.dark)"),
+                scope_theme_css(&light, ":root.light"),
+                scope_theme_css(&dark, ":root:not(.light)"),
+                scope_theme_css(&dark, ":root.dark")
+            ),
         }
+    }
+
+    pub fn init_extra_syntaxes_and_highlight_themes(&mut self, path: &Path) -> Result<()> {
+        let theme_names = match self.highlight_theme {
+            HighlightTheme::Single(ref name) if name == "css" => return Ok(()),
+            HighlightTheme::Single(ref name) => vec![name.clone()],
+            HighlightTheme::LightDark(ref themes) => {
+                vec![themes.light.clone(), themes.dark.clone()]
+            }
+        };
 
         let (loaded_extra_syntaxes, loaded_extra_highlight_themes) =
             self.load_extra_syntaxes_and_highlight_themes(path)?;
@@ -131,17 +291,16 @@ impl Markdown {
         }
 
         // Validate that the chosen highlight_theme exists in the loaded highlight theme sets
-        if !THEME_SET.themes.contains_key(&self.highlight_theme) {
-            if let Some(extra) = &*self.extra_theme_set {
-                if !extra.themes.contains_key(&self.highlight_theme) {
-                    bail!(
-                        "Highlight theme {} not found in the extra theme set",
-                        self.highlight_theme
-                    )
+        for theme_name in theme_names {
+            if !THEME_SET.themes.contains_key(&theme_name) {
+                if let Some(extra) = &*self.extra_theme_set {
+                    if !extra.themes.contains_key(&theme_name) {
+                        bail!("Highlight theme {} not found in the extra theme set", theme_name)
+                    }
+                } else {
+                    bail!("Highlight theme {} not available.\n\
+                    You can load custom themes by configuring `extra_syntaxes_and_themes` to include a list of folders containing '.tmTheme' files", theme_name)
                 }
-            } else {
-                bail!("Highlight theme {} not available.\n\
-                You can load custom themes by configuring `extra_syntaxes_and_themes` to include a list of folders containing '.tmTheme' files", self.highlight_theme)
             }
         }
 
@@ -198,14 +357,24 @@ impl Default for Markdown {
     fn default() -> Markdown {
         Markdown {
             highlight_code: false,
-            highlight_theme: DEFAULT_HIGHLIGHT_THEME.to_owned(),
+            highlight_theme: HighlightTheme::Single(DEFAULT_HIGHLIGHT_THEME.to_owned()),
             highlight_themes_css: Vec::new(),
+            highlighter: HighlightBackend::Syntect,
+            language_highlighters: HashMap::new(),
+            fence_processors: HashMap::new(),
+            fence_processors_cache_dir: None,
+            code_line_anchors: false,
             render_emoji: false,
+            render_admonitions: false,
+            wiki_links: false,
             external_links_target_blank: false,
             external_links_no_follow: false,
             external_links_no_referrer: false,
             render_with_section_tags: None,
             smart_punctuation: false,
+            toc_min_level: 1,
+            toc_max_level: 6,
+            responsive_images: None,
             extra_syntaxes_and_themes: vec![],
             extra_syntax_set: None,
             extra_theme_set: Arc::new(None),
@@ -1,6 +1,10 @@
+pub mod compression;
+pub mod fingerprint;
 pub mod languages;
 pub mod link_checker;
+pub mod load_data;
 pub mod markup;
+pub mod redirects;
 pub mod search;
 pub mod slugify;
 pub mod taxonomies;
@@ -62,8 +66,20 @@ pub struct Config {
     pub compile_sass: bool,
     /// Whether to minify the html output
     pub minify_html: bool,
+    /// Whether to minify the compiled Sass, the static CSS files matching `minify_static`
+    /// and the inline `<style>` elements
+    pub minify_css: bool,
+    /// Whether to minify the static JavaScript files matching `minify_static` and the inline
+    /// `<script>` elements
+    pub minify_js: bool,
+    /// Glob patterns of the static files to minify, relative to the `static` directory
+    pub minify_static: Vec<String>,
+    /// Whether to write a source map next to every minified CSS and JavaScript file
+    pub minify_source_maps: bool,
     /// Whether to build the search index for the content
     pub build_search_index: bool,
+    /// Whether to write a `build-manifest.json` listing the output files
+    pub generate_build_manifest: bool,
     /// A list of file glob patterns to ignore when processing the content folder. Defaults to none.
     /// Had to remove the PartialEq derive because GlobSet does not implement it. No impact
     /// because it's unused anyway (who wants to sort Configs?).
@@ -79,6 +95,14 @@ pub struct Config {
     pub output_dir: String,
 
     pub link_checker: link_checker::LinkChecker,
+    /// The cache and fixtures of the remote data loaded with `load_data`
+    pub load_data: load_data::LoadData,
+    /// Which assets get the hash of their content in their names
+    pub fingerprint: fingerprint::Fingerprint,
+    /// Whether to write gzip and brotli copies of the output files
+    pub compress_output: compression::Compression,
+    /// How to redirect the `aliases` and `redirect_to` of the content
+    pub redirects: redirects::Redirects,
     /// The setup for which slugification strategies to use for paths, taxonomies and anchors
     pub slugify: slugify::Slugify,
     /// The search config, telling what to include in the search index
@@ -125,6 +149,18 @@ impl Config {
 
         config.add_default_language();
 
+        if config.markdown.toc_min_level > config.markdown.toc_max_level {
+            bail!("`markdown.toc_min_level` can't be greater than `markdown.toc_max_level`");
+        }
+
+        if let Some(ref responsive_images) = config.markdown.responsive_images {
+            if let Some(quality) = responsive_images.quality {
+                if quality == 0 || quality > 100 {
+                    bail!("`markdown.responsive_images.quality` must be in range 1-100");
+                }
+            }
+        }
+
         if !config.ignored_content.is_empty() {
             // Convert the file glob strings into a compiled glob set matcher. We want to do this once,
             // at program initialization, rather than for every page, for example. We arrange for the
@@ -142,6 +178,10 @@ impl Config {
             config.ignored_content_globset =
                 Some(glob_set_builder.build().expect("Bad ignored_content in config file."));
         }
+        // Only to report invalid patterns early, the glob set is used when loading the site
+        config.fingerprint.globset()?;
+        config.minify_static_globset()?;
+        config.redirects.validate()?;
 
         Ok(config)
     }
@@ -256,6 +296,23 @@ impl Config {
         self.mode == Mode::Check
     }
 
+    /// The glob set of the static files to minify if they are CSS or JavaScript
+    pub fn minify_static_globset(&self) -> Result<GlobSet> {
+        let mut glob_set_builder = GlobSetBuilder::new();
+        for pat in &self.minify_static {
+            let glob = match Glob::new(pat) {
+                Ok(g) => g,
+                Err(e) => bail!("Invalid minify_static glob pattern: {}, error = {}", pat, e),
+            };
+            glob_set_builder.add(glob);
+        }
+        Ok(glob_set_builder.build().expect("Bad minify_static in config file."))
+    }
+
+    pub fn is_in_serve_mode(&self) -> bool {
+        self.mode == Mode::Serve
+    }
+
     pub fn enable_serve_mode(&mut self) {
         self.mode = Mode::Serve;
     }
@@ -346,13 +403,22 @@ impl Default for Config {
             taxonomies: Vec::new(),
             compile_sass: false,
             minify_html: false,
+            minify_css: false,
+            minify_js: false,
+            minify_static: vec!["**/*.css".to_string(), "**/*.js".to_string()],
+            minify_source_maps: false,
             mode: Mode::Build,
             build_search_index: false,
+            generate_build_manifest: false,
             ignored_content: Vec::new(),
             ignored_content_globset: None,
             translations: HashMap::new(),
             output_dir: "public".to_string(),
             link_checker: link_checker::LinkChecker::default(),
+            load_data: load_data::LoadData::default(),
+            fingerprint: fingerprint::Fingerprint::default(),
+            compress_output: compression::Compression::default(),
+            redirects: redirects::Redirects::default(),
             slugify: slugify::Slugify::default(),
             search: search::Search::default(),
             markdown: markup::Markdown::default(),
@@ -618,6 +684,143 @@ skip_prefixes = [
         );
     }
 
+    #[test]
+    fn can_parse_load_data_cache() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[load_data]
+cache = true
+cache_ttl = 60
+fixtures_dir = "tests/fixtures"
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.load_data.cache);
+        assert_eq!(config.load_data.cache_dir, ".zola-cache");
+        assert_eq!(config.load_data.cache_ttl, 60);
+        assert_eq!(config.load_data.fixtures_dir, Some("tests/fixtures".to_string()));
+        assert!(!config.load_data.offline);
+    }
+
+    #[test]
+    fn can_fingerprint_links() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[fingerprint]
+enabled = true
+files = ["css/*.css"]
+        "#;
+
+        let mut config = Config::parse(config_str).unwrap();
+        assert!(config.fingerprint.enabled);
+        assert!(config.fingerprint.globset().unwrap().is_match("css/site.css"));
+        assert!(!config.fingerprint.globset().unwrap().is_match("js/site.js"));
+
+        config
+            .fingerprint
+            .manifest
+            .insert("css/site.css".to_string(), "css/site.0123456789abcdef.css".to_string());
+        assert_eq!(
+            config.fingerprint.fingerprint_link("/css/site.css?v=1#top"),
+            Some("/css/site.0123456789abcdef.css?v=1#top".to_string())
+        );
+        assert_eq!(config.fingerprint.fingerprint_link("css/site.css"), None);
+        assert_eq!(config.fingerprint.fingerprint_link("/css/print.css"), None);
+    }
+
+    #[test]
+    fn errors_on_invalid_fingerprint_glob() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[fingerprint]
+files = ["css/[.css"]
+        "#;
+
+        assert!(Config::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn can_parse_minify_options() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+minify_css = true
+minify_static = ["css/*.css"]
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.minify_css);
+        assert!(!config.minify_js);
+        assert!(!config.minify_source_maps);
+        assert!(config.minify_static_globset().unwrap().is_match("css/site.css"));
+        assert!(!config.minify_static_globset().unwrap().is_match("js/site.js"));
+
+        let config = Config::parse("title = \"My site\"\nminify_static = [\"[.css\"]");
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn can_parse_compress_output() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[compress_output]
+enabled = true
+brotli = false
+min_size = 10
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.compress_output.enabled);
+        assert!(config.compress_output.gzip);
+        assert!(!config.compress_output.brotli);
+        assert!(config.compress_output.should_compress("html", 10));
+        assert!(!config.compress_output.should_compress("html", 9));
+        assert!(!config.compress_output.should_compress("png", 1000));
+    }
+
+    #[test]
+    fn can_parse_redirects() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[redirects]
+formats = ["netlify", "json"]
+html = false
+redirect_to_status = 302
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert_eq!(
+            config.redirects.formats,
+            vec![redirects::RedirectFormat::Netlify, redirects::RedirectFormat::Json]
+        );
+        assert!(!config.redirects.html);
+        assert_eq!(config.redirects.aliases_status, 301);
+        assert_eq!(config.redirects.redirect_to_status, 302);
+    }
+
+    #[test]
+    fn errors_on_invalid_redirect_status() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[redirects]
+aliases_status = 200
+        "#;
+
+        assert!(Config::parse(config_str).is_err());
+    }
+
     #[test]
     fn slugify_strategies() {
         let config_str = r#"
@@ -706,6 +909,116 @@ highlight_themes_css = [
         assert_eq!(config.is_err(), true);
     }
 
+    #[test]
+    fn can_configure_responsive_images() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown.responsive_images]
+widths = [480, 960]
+formats = ["webp", "auto"]
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        let responsive_images = config.markdown.responsive_images.unwrap();
+        assert_eq!(responsive_images.widths, vec![480, 960]);
+        assert_eq!(responsive_images.formats, vec!["webp", "auto"]);
+        assert_eq!(responsive_images.sizes, "100vw");
+    }
+
+    #[test]
+    fn errors_on_invalid_responsive_images_quality() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown.responsive_images]
+widths = [480]
+quality = 0
+        "#;
+
+        let config = Config::parse(config);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn errors_on_invalid_toc_levels() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown]
+toc_min_level = 3
+toc_max_level = 2
+        "#;
+
+        let config = Config::parse(config);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn can_configure_light_and_dark_highlight_themes() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown]
+highlight_code = true
+highlight_theme = { light = "base16-ocean-light", dark = "base16-ocean-dark", dark_mode = "class" }
+        "#;
+
+        let mut config = Config::parse(config).unwrap();
+        config.markdown.init_extra_syntaxes_and_highlight_themes(Path::new("")).unwrap();
+        assert!(config.markdown.get_highlight_theme().is_none());
+        let themes = match config.markdown.highlight_theme {
+            markup::HighlightTheme::LightDark(ref themes) => themes.clone(),
+            _ => panic!("Expected light and dark themes"),
+        };
+        assert_eq!(themes.filename, "syntax-theme.css");
+        assert_eq!(themes.dark_mode, markup::DarkMode::Class);
+
+        let css = config.markdown.export_light_dark_theme_css(&themes);
+        assert!(css.contains(":root:not(.dark) .z-code {"));
+        assert!(css
+            .contains(":root.dark .z-comment, :root.dark .z-punctuation.z-definition.z-comment {"));
+        assert!(!css.contains("@media"));
+    }
+
+    #[test]
+    fn errors_on_unknown_light_or_dark_highlight_theme() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown]
+highlight_code = true
+highlight_theme = { light = "base16-ocean-light", dark = "asdf" }
+        "#;
+
+        let mut config = Config::parse(config).unwrap();
+        assert!(config.markdown.init_extra_syntaxes_and_highlight_themes(Path::new("")).is_err());
+    }
+
+    #[test]
+    fn can_select_highlighter_per_language() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown.language_highlighters]
+rust = "tree-sitter"
+python = "syntect"
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.markdown.highlighter, markup::HighlightBackend::Syntect);
+        assert_eq!(
+            config.markdown.language_highlighters["rust"],
+            markup::HighlightBackend::TreeSitter
+        );
+    }
+
     // https://github.com/getzola/zola/issues/1687
     #[test]
     fn regression_config_default_lang_data() {
@@ -0,0 +1,55 @@
+use serde_derive::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// The status codes that make sense for redirecting a page to another one
+const REDIRECT_STATUSES: [u16; 5] = [301, 302, 303, 307, 308];
+
+/// The server-side redirect files that can be written
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectFormat {
+    /// A `_redirects` file, for Netlify and Cloudflare Pages
+    Netlify,
+    /// A `redirects.map` file to include in the nginx config
+    Nginx,
+    /// A `.htaccess` file for Apache
+    Apache,
+    /// A `redirects.json` file listing the redirects
+    Json,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Redirects {
+    /// The redirect files to write from the `aliases` and `redirect_to` of the content
+    pub formats: Vec<RedirectFormat>,
+    /// Whether to write the HTML pages redirecting with a meta refresh. They are always
+    /// written by `zola serve`
+    pub html: bool,
+    /// The HTTP status code of the redirects from `aliases`
+    pub aliases_status: u16,
+    /// The HTTP status code of the redirects from `redirect_to`
+    pub redirect_to_status: u16,
+}
+
+impl Redirects {
+    pub fn validate(&self) -> Result<()> {
+        for status in &[self.aliases_status, self.redirect_to_status] {
+            if !REDIRECT_STATUSES.contains(status) {
+                bail!(
+                    "Invalid redirect status code {}, it should be one of {:?}",
+                    status,
+                    REDIRECT_STATUSES
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Redirects {
+    fn default() -> Redirects {
+        Redirects { formats: Vec::new(), html: true, aliases_status: 301, redirect_to_status: 301 }
+    }
+}
@@ -3,8 +3,20 @@ pub mod highlighting;
 mod theme;
 
 pub use crate::config::{
-    languages::LanguageOptions, link_checker::LinkChecker, search::Search, slugify::Slugify,
-    taxonomies::Taxonomy, markup::SectionTagsMode, Config,
+    compression::Compression,
+    fingerprint::Fingerprint,
+    languages::LanguageOptions,
+    link_checker::LinkChecker,
+    load_data::LoadData,
+    markup::{
+        DarkMode, FenceProcessor, HighlightBackend, HighlightTheme, LightDarkThemes,
+        ResponsiveImages, SectionTagsMode,
+    },
+    redirects::{RedirectFormat, Redirects},
+    search::Search,
+    slugify::Slugify,
+    taxonomies::Taxonomy,
+    Config,
 };
 use errors::Result;
 
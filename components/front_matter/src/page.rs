@@ -58,6 +58,10 @@ pub struct PageFrontMatter {
     /// Defaults to `true` but is only used if search if explicitly enabled in the config.
     #[serde(skip_serializing)]
     pub in_search_index: bool,
+    /// The minimum heading level included in the table of contents, overriding the config value
+    pub toc_min_level: Option<u32>,
+    /// The maximum heading level included in the table of contents, overriding the config value
+    pub toc_max_level: Option<u32>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -68,7 +72,7 @@ pub struct PageFrontMatter {
 /// 2. a local datetime (RFC3339 with timezone omitted)
 /// 3. a local date (YYYY-MM-DD).
 /// This tries each in order.
-fn parse_datetime(d: &str) -> Option<NaiveDateTime> {
+pub fn parse_datetime(d: &str) -> Option<NaiveDateTime> {
     DateTime::parse_from_rfc3339(d)
         .or_else(|_| DateTime::parse_from_rfc3339(format!("{}Z", d).as_ref()))
         .map(|s| s.naive_local())
@@ -92,6 +96,12 @@ impl PageFrontMatter {
             }
         }
 
+        if let (Some(min), Some(max)) = (f.toc_min_level, f.toc_max_level) {
+            if min > max {
+                bail!("`toc_min_level` can't be greater than `toc_max_level`")
+            }
+        }
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in page extra"),
@@ -143,6 +153,8 @@ impl Default for PageFrontMatter {
             aliases: Vec::new(),
             in_search_index: true,
             template: None,
+            toc_min_level: None,
+            toc_max_level: None,
             extra: Map::new(),
         }
     }
@@ -216,6 +228,19 @@ path: ""
         assert!(res.is_err());
     }
 
+    #[test_case(&RawFrontMatter::Toml(r#"
+toc_min_level = 3
+toc_max_level = 2
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+toc_min_level: 3
+toc_max_level: 2
+"#); "yaml")]
+    fn errors_on_inverted_toc_levels(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content);
+        assert!(res.is_err());
+    }
+
     #[test_case(&RawFrontMatter::Toml(r#"
 title = "Hello"
 description = "hey there"
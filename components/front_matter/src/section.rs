@@ -2,7 +2,7 @@ use serde_derive::{Deserialize, Serialize};
 use tera::{Map, Value};
 
 use super::{InsertAnchor, SortBy};
-use errors::Result;
+use errors::{bail, Result};
 use utils::de::fix_toml_dates;
 
 use crate::RawFrontMatter;
@@ -70,6 +70,12 @@ pub struct SectionFrontMatter {
     /// Whether to generate a feed for the current section
     #[serde(skip_serializing)]
     pub generate_feed: bool,
+    /// The minimum heading level included in the table of contents, overriding the config value
+    #[serde(skip_serializing)]
+    pub toc_min_level: Option<u32>,
+    /// The maximum heading level included in the table of contents, overriding the config value
+    #[serde(skip_serializing)]
+    pub toc_max_level: Option<u32>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -78,6 +84,12 @@ impl SectionFrontMatter {
     pub fn parse(raw: &RawFrontMatter) -> Result<SectionFrontMatter> {
         let mut f: SectionFrontMatter = raw.deserialize()?;
 
+        if let (Some(min), Some(max)) = (f.toc_min_level, f.toc_max_level) {
+            if min > max {
+                bail!("`toc_min_level` can't be greater than `toc_max_level`")
+            }
+        }
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in section extra"),
@@ -114,6 +126,8 @@ impl Default for SectionFrontMatter {
             page_template: None,
             aliases: Vec::new(),
             generate_feed: false,
+            toc_min_level: None,
+            toc_max_level: None,
             extra: Map::new(),
             draft: false,
         }
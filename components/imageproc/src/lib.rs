@@ -400,6 +400,10 @@ impl Processor {
         }
     }
 
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
     pub fn set_base_url(&mut self, config: &Config) {
         self.base_url = config.make_permalink(RESIZED_SUBDIR);
     }
@@ -1,18 +1,19 @@
 /// A page, can be a blog post or a basic page
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 use slotmap::DefaultKey;
-use tera::{Context as TeraContext, Tera};
+use tera::{Context as TeraContext, Tera, Value};
 
 use crate::library::Library;
 use config::Config;
 use errors::{Error, Result};
 use front_matter::{split_page_content, InsertAnchor, PageFrontMatter};
 use rendering::{render_content, Heading, RenderContext};
-use utils::site::get_reading_analytics;
+use utils::site::{get_reading_analytics, WikiLinkTargets};
 use utils::slugs::slugify_paths;
 use utils::templates::{render_template, ShortcodeDefinition};
 
@@ -41,6 +42,8 @@ pub struct Page {
     pub ancestors: Vec<DefaultKey>,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// The number of lines before the content in the file, i.e. the front matter
+    pub content_line_offset: usize,
     /// All the non-md files we found next to the .md file
     pub assets: Vec<PathBuf>,
     /// All the non-md files we found next to the .md file
@@ -94,6 +97,9 @@ pub struct Page {
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The full path of the files included in code blocks, `zola serve` rebuilds the site when
+    /// one of them changes
+    pub included_files: Vec<PathBuf>,
 }
 
 impl Page {
@@ -112,12 +118,16 @@ impl Page {
         config: &Config,
         base_path: &Path,
     ) -> Result<Page> {
-        let (meta, content) = split_page_content(file_path, content)?;
+        let (meta, raw_content) = split_page_content(file_path, content)?;
+        // The content is what remains of the file after the front matter
+        let content_line_offset =
+            content[..content.len() - raw_content.len()].matches('\n').count();
         let mut page = Page::new(file_path, meta, base_path);
 
         page.lang = page.file.find_language(config)?;
 
-        page.raw_content = content.to_string();
+        page.raw_content = raw_content.to_string();
+        page.content_line_offset = content_line_offset;
         let (word_count, reading_time) = get_reading_analytics(&page.raw_content);
         page.word_count = Some(word_count);
         page.reading_time = Some(reading_time);
@@ -218,14 +228,22 @@ impl Page {
     }
 
     /// We need access to all pages url to render links relative to content
-    /// so that can't happen at the same time as parsing
+    /// so that can't happen at the same time as parsing.
+    /// `parent_section` is given to the shortcodes as `section`, already serialized as the
+    /// sections can't be borrowed while rendering the pages, and `data` as `data`
+    #[allow(clippy::too_many_arguments)]
     pub fn render_markdown(
         &mut self,
         permalinks: &HashMap<String, String>,
+        wiki_links: &WikiLinkTargets,
         tera: &Tera,
         config: &Config,
+        data: &Value,
+        base_path: &Path,
         anchor_insert: InsertAnchor,
+        parent_section: Option<&Value>,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+        imageproc: Option<&Mutex<imageproc::Processor>>,
     ) -> Result<()> {
         let mut context = RenderContext::new(
             tera,
@@ -236,8 +254,19 @@ impl Page {
             anchor_insert,
         );
         context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wiki_links(wiki_links);
+        context.set_line_offset(self.content_line_offset);
         context.set_current_page_path(&self.file.relative);
+        context.set_base_path(base_path);
+        context.set_toc_levels(self.meta.toc_min_level, self.meta.toc_max_level);
+        if let Some(imageproc) = imageproc {
+            context.set_image_processor(imageproc);
+        }
+        context.tera_context.insert("data", data);
         context.tera_context.insert("page", &SerializingPage::from_page_basic(self, None));
+        if let Some(parent_section) = parent_section {
+            context.tera_context.insert("section", parent_section);
+        }
 
         let res = render_content(&self.raw_content, &context).map_err(|e| {
             Error::chain(format!("Failed to render content of {}", self.file.path.display()), e)
@@ -251,12 +280,19 @@ impl Page {
         self.toc = res.toc;
         self.external_links = res.external_links;
         self.internal_links = res.internal_links;
+        self.included_files = res.included_files;
 
         Ok(())
     }
 
     /// Renders the page using the default layout, unless specified in front-matter
-    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+    pub fn render_html(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
         let tpl_name = match self.meta.template {
             Some(ref l) => l,
             None => "page.html",
@@ -264,6 +300,7 @@ impl Page {
 
         let mut context = TeraContext::new();
         context.insert("config", &config.serialize(&self.lang));
+        context.insert("data", data);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
         context.insert("page", &self.to_serialized(library));
@@ -318,7 +355,7 @@ mod tests {
 
     use globset::{Glob, GlobSetBuilder};
     use tempfile::tempdir;
-    use tera::Tera;
+    use tera::{Tera, Value};
 
     use super::Page;
     use config::{Config, LanguageOptions};
@@ -340,10 +377,15 @@ Hello world"#;
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &HashMap::new(),
             &Tera::default(),
             &config,
+            &Value::Null,
+            Path::new(""),
             InsertAnchor::None,
+            None,
             &HashMap::new(),
+            None,
         )
         .unwrap();
 
@@ -512,15 +554,93 @@ Hello world
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &HashMap::new(),
             &Tera::default(),
             &config,
+            &Value::Null,
+            Path::new(""),
             InsertAnchor::None,
+            None,
             &HashMap::new(),
+            None,
         )
         .unwrap();
         assert_eq!(page.summary, Some("<p>Hello world</p>\n".to_string()));
     }
 
+    #[test]
+    fn shortcode_errors_point_to_their_line_in_the_file() {
+        let config = Config::default_for_test();
+        let content = r#"
++++
+title = "Hello"
++++
+Hello world
+
+A {{ unknown() }}"#
+            .to_string();
+        let mut page =
+            Page::parse(Path::new("hello.md"), &content, &config, &PathBuf::new()).unwrap();
+        assert_eq!(page.content_line_offset, 4);
+        let err = page
+            .render_markdown(
+                &HashMap::default(),
+                &HashMap::new(),
+                &Tera::default(),
+                &config,
+                &Value::Null,
+                Path::new(""),
+                InsertAnchor::None,
+                None,
+                &HashMap::new(),
+                None,
+            )
+            .unwrap_err();
+        let reason = format!("{}", std::error::Error::source(&err).unwrap());
+        assert!(reason.ends_with(
+            "It is used at line 7, column 3:\n  |\n6 |\n7 | A {{ unknown() }}\n  |   ^^^^^^^^^^^^^^^"
+        ));
+    }
+
+    #[test]
+    fn shortcodes_can_access_the_page_and_its_section() {
+        let config = Config::default_for_test();
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "shortcodes/where.html",
+            "{{ page.title }} ({{ page.extra.kind }}) in {{ section.title }}",
+        )
+        .unwrap();
+        let shortcode_definitions = utils::templates::get_shortcodes(&tera);
+        let content = r#"
++++
+title = "Hello"
+[extra]
+kind = "post"
++++
+{{ where() }}"#
+            .to_string();
+        let mut page =
+            Page::parse(Path::new("hello.md"), &content, &config, &PathBuf::new()).unwrap();
+        let mut section = tera::Map::new();
+        section.insert("title".to_string(), tera::Value::String("Blog".to_string()));
+        let section = tera::Value::Object(section);
+        page.render_markdown(
+            &HashMap::default(),
+            &HashMap::new(),
+            &tera,
+            &config,
+            &Value::Null,
+            Path::new(""),
+            InsertAnchor::None,
+            Some(&section),
+            &shortcode_definitions,
+            None,
+        )
+        .unwrap();
+        assert_eq!(page.content, "Hello (post) in Blog");
+    }
+
     #[test]
     fn strips_footnotes_in_summary() {
         let config = Config::default_for_test();
@@ -542,10 +662,15 @@ And here's another. [^2]
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &HashMap::new(),
             &Tera::default(),
             &config,
+            &Value::Null,
+            Path::new(""),
             InsertAnchor::None,
+            None,
             &HashMap::new(),
+            None,
         )
         .unwrap();
         assert_eq!(
@@ -1,15 +1,16 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use slotmap::DefaultKey;
-use tera::{Context as TeraContext, Tera};
+use tera::{Context as TeraContext, Tera, Value};
 
 use config::Config;
 use errors::{Error, Result};
 use front_matter::{split_section_content, SectionFrontMatter};
 use rendering::{render_content, Heading, RenderContext};
 use utils::fs::read_file;
-use utils::site::get_reading_analytics;
+use utils::site::{get_reading_analytics, WikiLinkTargets};
 use utils::templates::{render_template, ShortcodeDefinition};
 
 use crate::content::file_info::FileInfo;
@@ -32,6 +33,8 @@ pub struct Section {
     pub permalink: String,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// The number of lines before the content in the file, i.e. the front matter
+    pub content_line_offset: usize,
     /// The HTML rendered of the page
     pub content: String,
     /// All the non-md files we found next to the .md file
@@ -62,6 +65,9 @@ pub struct Section {
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The full path of the files included in code blocks, `zola serve` rebuilds the site when
+    /// one of them changes
+    pub included_files: Vec<PathBuf>,
 }
 
 impl Section {
@@ -81,10 +87,14 @@ impl Section {
         config: &Config,
         base_path: &Path,
     ) -> Result<Section> {
-        let (meta, content) = split_section_content(file_path, content)?;
+        let (meta, raw_content) = split_section_content(file_path, content)?;
+        // The content is what remains of the file after the front matter
+        let content_line_offset =
+            content[..content.len() - raw_content.len()].matches('\n').count();
         let mut section = Section::new(file_path, meta, base_path);
         section.lang = section.file.find_language(config)?;
-        section.raw_content = content.to_string();
+        section.raw_content = raw_content.to_string();
+        section.content_line_offset = content_line_offset;
         let (word_count, reading_time) = get_reading_analytics(&section.raw_content);
         section.word_count = Some(word_count);
         section.reading_time = Some(reading_time);
@@ -142,12 +152,17 @@ impl Section {
 
     /// We need access to all pages url to render links relative to content
     /// so that can't happen at the same time as parsing
+    #[allow(clippy::too_many_arguments)]
     pub fn render_markdown(
         &mut self,
         permalinks: &HashMap<String, String>,
+        wiki_links: &WikiLinkTargets,
         tera: &Tera,
         config: &Config,
+        data: &Value,
+        base_path: &Path,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+        imageproc: Option<&Mutex<imageproc::Processor>>,
     ) -> Result<()> {
         let mut context = RenderContext::new(
             tera,
@@ -158,7 +173,15 @@ impl Section {
             self.meta.insert_anchor_links,
         );
         context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wiki_links(wiki_links);
+        context.set_line_offset(self.content_line_offset);
         context.set_current_page_path(&self.file.relative);
+        context.set_base_path(base_path);
+        context.set_toc_levels(self.meta.toc_min_level, self.meta.toc_max_level);
+        if let Some(imageproc) = imageproc {
+            context.set_image_processor(imageproc);
+        }
+        context.tera_context.insert("data", data);
         context.tera_context.insert("section", &SerializingSection::from_section_basic(self, None));
 
         let res = render_content(&self.raw_content, &context).map_err(|e| {
@@ -168,16 +191,24 @@ impl Section {
         self.toc = res.toc;
         self.external_links = res.external_links;
         self.internal_links = res.internal_links;
+        self.included_files = res.included_files;
 
         Ok(())
     }
 
     /// Renders the page using the default layout, unless specified in front-matter
-    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+    pub fn render_html(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
         let tpl_name = self.get_template_name();
 
         let mut context = TeraContext::new();
         context.insert("config", &config.serialize(&self.lang));
+        context.insert("data", data);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
         context.insert("section", &self.to_serialized(library));
@@ -9,5 +9,7 @@ pub use slotmap::{DenseSlotMap, Key};
 pub use crate::library::Library;
 pub use content::{Page, Section, SerializingPage, SerializingSection};
 pub use pagination::Paginator;
-pub use sorting::sort_actual_pages_by_date;
+pub use sorting::{
+    sort_actual_pages_by_date, sort_pages_by_date, sort_pages_by_title, sort_pages_by_weight,
+};
 pub use taxonomies::{find_taxonomies, Taxonomy, TaxonomyItem};
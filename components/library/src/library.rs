@@ -9,6 +9,7 @@ use crate::sorting::{
 };
 use config::Config;
 use front_matter::{PageFrontMatter, SortBy};
+use utils::site::WikiLinkTargets;
 
 // Like vec! but for HashSet
 macro_rules! set {
@@ -437,6 +438,22 @@ impl Library {
             })
             .collect()
     }
+
+    /// Indexes the pages by lowercased slug and title, to resolve the `[[wiki links]]`
+    pub fn wiki_link_targets(&self) -> WikiLinkTargets {
+        let mut targets: WikiLinkTargets = HashMap::new();
+        for page in self.pages.values() {
+            let target = (page.lang.clone(), page.file.relative.clone());
+            let names = std::iter::once(&page.slug).chain(page.meta.title.as_ref());
+            for name in names {
+                let entry = targets.entry(name.trim().to_lowercase()).or_default();
+                if !entry.contains(&target) {
+                    entry.push(target.clone());
+                }
+            }
+        }
+        targets
+    }
 }
 
 #[cfg(test)]
@@ -497,4 +514,23 @@ mod tests {
         assert!(collisions[0].1.contains(&page.file.relative));
         assert!(collisions[0].1.contains(&page2.file.relative));
     }
+
+    #[test]
+    fn can_index_pages_for_wiki_links() {
+        let mut library = Library::new(10, 10, false);
+        let mut page =
+            Page { slug: "hello".to_string(), lang: "en".to_string(), ..Default::default() };
+        page.file.relative = "hello.md".to_string();
+        page.meta.title = Some("Hello".to_string());
+        let mut page2 =
+            Page { slug: "world".to_string(), lang: "en".to_string(), ..Default::default() };
+        page2.file.relative = "world.md".to_string();
+        page2.meta.title = Some("Hello".to_string());
+        library.insert_page(page);
+        library.insert_page(page2);
+
+        let targets = library.wiki_link_targets();
+        assert_eq!(targets["hello"].len(), 2);
+        assert_eq!(targets["world"], vec![("en".to_string(), "world.md".to_string())]);
+    }
 }
@@ -227,6 +227,7 @@ impl<'a> Paginator<'a> {
         &self,
         pager: &Pager,
         config: &Config,
+        data: &Value,
         tera: &Tera,
         library: &Library,
     ) -> Result<String> {
@@ -237,12 +238,14 @@ impl<'a> Paginator<'a> {
                     .insert("section", &SerializingSection::from_section_basic(s, Some(library)));
                 context.insert("lang", &s.lang);
                 context.insert("config", &config.serialize(&s.lang));
+                context.insert("data", data);
             }
             PaginationRoot::Taxonomy(t, item) => {
                 context.insert("taxonomy", &t.kind);
                 context.insert("term", &item.serialize(library));
                 context.insert("lang", &t.lang);
                 context.insert("config", &config.serialize(&t.lang));
+                context.insert("data", data);
             }
         };
         context.insert("current_url", &pager.permalink);
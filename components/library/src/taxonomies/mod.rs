@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 use serde_derive::Serialize;
 use slotmap::DefaultKey;
-use tera::{Context, Tera};
+use tera::{Context, Tera, Value};
 
 use config::{Config, Taxonomy as TaxonomyConfig};
 use errors::{bail, Error, Result};
@@ -189,10 +189,12 @@ impl Taxonomy {
         item: &TaxonomyItem,
         tera: &Tera,
         config: &Config,
+        data: &Value,
         library: &Library,
     ) -> Result<String> {
         let mut context = Context::new();
         context.insert("config", &config.serialize(&self.lang));
+        context.insert("data", data);
         context.insert("lang", &self.lang);
         context.insert("term", &SerializedTaxonomyItem::from_item(item, library));
         context.insert("taxonomy", &self.kind);
@@ -218,10 +220,12 @@ impl Taxonomy {
         &self,
         tera: &Tera,
         config: &Config,
+        data: &Value,
         library: &Library,
     ) -> Result<String> {
         let mut context = Context::new();
         context.insert("config", &config.serialize(&self.lang));
+        context.insert("data", data);
         let terms: Vec<SerializedTaxonomyItem> =
             self.items.iter().map(|i| SerializedTaxonomyItem::from_item(i, library)).collect();
         context.insert("terms", &terms);
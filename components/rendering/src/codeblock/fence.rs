@@ -24,6 +24,13 @@ pub struct FenceSettings<'a> {
     pub line_number_start: usize,
     pub highlight_lines: Vec<RangeInclusive<usize>>,
     pub hide_lines: Vec<RangeInclusive<usize>>,
+    pub name: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub line_anchors: bool,
+    /// A file to use as the content of the code block, relative to the site directory
+    pub include: Option<&'a str>,
+    pub include_lines: Option<RangeInclusive<usize>>,
+    pub include_region: Option<&'a str>,
 }
 
 impl<'a> FenceSettings<'a> {
@@ -34,6 +41,12 @@ impl<'a> FenceSettings<'a> {
             line_number_start: 1,
             highlight_lines: Vec::new(),
             hide_lines: Vec::new(),
+            name: None,
+            title: None,
+            line_anchors: false,
+            include: None,
+            include_lines: None,
+            include_region: None,
         };
 
         for token in FenceIter::new(fence_info) {
@@ -43,6 +56,12 @@ impl<'a> FenceSettings<'a> {
                 FenceToken::InitialLineNumber(l) => me.line_number_start = l,
                 FenceToken::HighlightLines(lines) => me.highlight_lines.extend(lines),
                 FenceToken::HideLines(lines) => me.hide_lines.extend(lines),
+                FenceToken::Name(name) => me.name = Some(name),
+                FenceToken::Title(title) => me.title = Some(title),
+                FenceToken::EnableLineAnchors => me.line_anchors = true,
+                FenceToken::Include(path) => me.include = Some(path),
+                FenceToken::IncludeLines(lines) => me.include_lines = Some(lines),
+                FenceToken::IncludeRegion(region) => me.include_region = Some(region),
             }
         }
 
@@ -57,15 +76,40 @@ enum FenceToken<'a> {
     InitialLineNumber(usize),
     HighlightLines(Vec<RangeInclusive<usize>>),
     HideLines(Vec<RangeInclusive<usize>>),
+    Name(&'a str),
+    Title(&'a str),
+    EnableLineAnchors,
+    Include(&'a str),
+    IncludeLines(RangeInclusive<usize>),
+    IncludeRegion(&'a str),
 }
 
 struct FenceIter<'a> {
-    split: std::str::Split<'a, char>,
+    rest: Option<&'a str>,
 }
 
 impl<'a> FenceIter<'a> {
     fn new(fence_info: &'a str) -> Self {
-        Self { split: fence_info.split(',') }
+        Self { rest: Some(fence_info) }
+    }
+
+    /// Returns the text up to the next `,` that isn't between quotes, so that quoted values
+    /// like `title="Hello, world"` can contain commas
+    fn next_token(&mut self) -> Option<&'a str> {
+        let rest = self.rest?;
+        let mut in_quotes = false;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => {
+                    self.rest = Some(&rest[i + 1..]);
+                    return Some(&rest[..i]);
+                }
+                _ => (),
+            }
+        }
+        self.rest = None;
+        Some(rest)
     }
 
     fn parse_ranges(token: Option<&str>) -> Vec<RangeInclusive<usize>> {
@@ -77,6 +121,17 @@ impl<'a> FenceIter<'a> {
         }
         ranges
     }
+
+    /// Removes the optional quotes around a string value, e.g. `name="src/main.rs"`
+    fn parse_string(token: Option<&'a str>) -> Option<&'a str> {
+        let value = token?.trim();
+        let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+        if value.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    }
 }
 
 impl<'a> Iterator for FenceIter<'a> {
@@ -84,9 +139,9 @@ impl<'a> Iterator for FenceIter<'a> {
 
     fn next(&mut self) -> Option<FenceToken<'a>> {
         loop {
-            let tok = self.split.next()?.trim();
+            let tok = self.next_token()?.trim();
 
-            let mut tok_split = tok.split('=');
+            let mut tok_split = tok.splitn(2, '=');
             match tok_split.next().unwrap_or("").trim() {
                 "" => continue,
                 "linenostart" => {
@@ -95,6 +150,33 @@ impl<'a> Iterator for FenceIter<'a> {
                     }
                 }
                 "linenos" => return Some(FenceToken::EnableLineNumbers),
+                "lineanchors" => return Some(FenceToken::EnableLineAnchors),
+                "name" => {
+                    if let Some(name) = Self::parse_string(tok_split.next()) {
+                        return Some(FenceToken::Name(name));
+                    }
+                }
+                "title" => {
+                    if let Some(title) = Self::parse_string(tok_split.next()) {
+                        return Some(FenceToken::Title(title));
+                    }
+                }
+                "include" => {
+                    if let Some(path) = Self::parse_string(tok_split.next()) {
+                        return Some(FenceToken::Include(path));
+                    }
+                }
+                "lines" => {
+                    if let Some(lines) = Self::parse_string(tok_split.next()).and_then(parse_range)
+                    {
+                        return Some(FenceToken::IncludeLines(lines));
+                    }
+                }
+                "region" => {
+                    if let Some(region) = Self::parse_string(tok_split.next()) {
+                        return Some(FenceToken::IncludeRegion(region));
+                    }
+                }
                 "hl_lines" => {
                     let ranges = Self::parse_ranges(tok_split.next());
                     return Some(FenceToken::HighlightLines(ranges));
@@ -0,0 +1,157 @@
+use lazy_static::lazy_static;
+use tree_sitter_highlight::HighlightConfiguration;
+
+use config::{Config, HighlightBackend};
+use errors::{bail, Result};
+
+/// The tree-sitter captures we highlight, with the TextMate scope they are styled as so
+/// the same themes and exported CSS work with both backends
+pub const TREE_SITTER_SCOPES: &[(&str, &str)] = &[
+    ("attribute", "entity.other.attribute-name"),
+    ("comment", "comment"),
+    ("constant", "constant"),
+    ("constant.builtin", "constant.language"),
+    ("constructor", "entity.name.type"),
+    ("embedded", "meta.embedded"),
+    ("escape", "constant.character.escape"),
+    ("function", "entity.name.function"),
+    ("function.builtin", "support.function"),
+    ("function.macro", "support.macro"),
+    ("keyword", "keyword"),
+    ("label", "entity.name.label"),
+    ("number", "constant.numeric"),
+    ("operator", "keyword.operator"),
+    ("property", "variable.other.member"),
+    ("punctuation.bracket", "punctuation.section"),
+    ("punctuation.delimiter", "punctuation.separator"),
+    ("punctuation.special", "punctuation.special"),
+    ("string", "string"),
+    ("string.special", "string.regexp"),
+    ("tag", "entity.name.tag"),
+    ("type", "entity.name.type"),
+    ("type.builtin", "storage.type"),
+    ("variable", "variable"),
+    ("variable.builtin", "variable.language"),
+    ("variable.parameter", "variable.parameter"),
+];
+
+/// A tree-sitter grammar bundled in Zola
+pub struct TreeSitterGrammar {
+    /// The fence languages using that grammar
+    pub tokens: &'static [&'static str],
+    pub config: HighlightConfiguration,
+}
+
+impl TreeSitterGrammar {
+    fn new(
+        tokens: &'static [&'static str],
+        language: tree_sitter::Language,
+        highlights: &[&str],
+        injections: &str,
+        locals: &[&str],
+    ) -> Self {
+        let mut config = HighlightConfiguration::new(
+            language,
+            &highlights.concat(),
+            injections,
+            &locals.concat(),
+        )
+        .expect("Failed to load the bundled tree-sitter queries");
+        let names: Vec<_> = TREE_SITTER_SCOPES.iter().map(|(name, _)| *name).collect();
+        config.configure(&names);
+        Self { tokens, config }
+    }
+}
+
+lazy_static! {
+    pub static ref TREE_SITTER_GRAMMARS: Vec<TreeSitterGrammar> = vec![
+        TreeSitterGrammar::new(
+            &["rust", "rs"],
+            tree_sitter_rust::language(),
+            &[tree_sitter_rust::HIGHLIGHT_QUERY],
+            tree_sitter_rust::INJECTIONS_QUERY,
+            &[],
+        ),
+        TreeSitterGrammar::new(
+            &["javascript", "js", "jsx"],
+            tree_sitter_javascript::language(),
+            &[tree_sitter_javascript::JSX_HIGHLIGHT_QUERY, tree_sitter_javascript::HIGHLIGHT_QUERY],
+            tree_sitter_javascript::INJECTION_QUERY,
+            &[tree_sitter_javascript::LOCALS_QUERY],
+        ),
+        TreeSitterGrammar::new(
+            &["typescript", "ts"],
+            tree_sitter_typescript::language_typescript(),
+            &[tree_sitter_typescript::HIGHLIGHT_QUERY, tree_sitter_javascript::HIGHLIGHT_QUERY],
+            tree_sitter_javascript::INJECTION_QUERY,
+            &[tree_sitter_typescript::LOCALS_QUERY, tree_sitter_javascript::LOCALS_QUERY],
+        ),
+        TreeSitterGrammar::new(
+            &["tsx"],
+            tree_sitter_typescript::language_tsx(),
+            &[
+                tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+                tree_sitter_typescript::HIGHLIGHT_QUERY,
+                tree_sitter_javascript::HIGHLIGHT_QUERY,
+            ],
+            tree_sitter_javascript::INJECTION_QUERY,
+            &[tree_sitter_typescript::LOCALS_QUERY, tree_sitter_javascript::LOCALS_QUERY],
+        ),
+    ];
+}
+
+/// Finds the bundled tree-sitter grammar for a fence language, if there is one
+pub fn find_tree_sitter_grammar(language: &str) -> Option<&'static HighlightConfiguration> {
+    TREE_SITTER_GRAMMARS.iter().find(|g| g.tokens.contains(&language)).map(|g| &g.config)
+}
+
+/// The grammar highlighting a fence language, if it uses the tree-sitter backend and
+/// there is one for it. Other languages are highlighted with syntect.
+pub(crate) fn resolve_tree_sitter_grammar(
+    language: Option<&str>,
+    config: &Config,
+) -> Option<&'static HighlightConfiguration> {
+    let lang = language?;
+    match config.markdown.language_highlighter(lang) {
+        HighlightBackend::TreeSitter => find_tree_sitter_grammar(lang),
+        HighlightBackend::Syntect => None,
+    }
+}
+
+/// Errors if a language is set to be highlighted with tree-sitter in
+/// `markdown.language_highlighters` but there is no grammar for it
+pub fn check_language_highlighters(config: &Config) -> Result<()> {
+    for (lang, backend) in &config.markdown.language_highlighters {
+        if *backend == HighlightBackend::TreeSitter && find_tree_sitter_grammar(lang).is_none() {
+            bail!("There is no tree-sitter grammar for the language `{}`", lang);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_load_bundled_grammars() {
+        for grammar in TREE_SITTER_GRAMMARS.iter() {
+            assert!(!grammar.config.names().is_empty());
+        }
+    }
+
+    #[test]
+    fn errors_on_language_without_tree_sitter_grammar() {
+        let mut config = Config::default();
+        config
+            .markdown
+            .language_highlighters
+            .insert("rust".to_owned(), HighlightBackend::TreeSitter);
+        assert!(check_language_highlighters(&config).is_ok());
+        config
+            .markdown
+            .language_highlighters
+            .insert("python".to_owned(), HighlightBackend::TreeSitter);
+        assert!(check_language_highlighters(&config).is_err());
+    }
+}
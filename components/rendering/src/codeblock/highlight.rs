@@ -2,12 +2,15 @@ use std::fmt::Write;
 
 use config::highlighting::{SyntaxAndTheme, CLASS_STYLE};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Color, Theme};
+use syntect::highlighting::{Color, FontStyle, Theme};
 use syntect::html::{
     line_tokens_to_classed_spans, styled_line_to_highlighted_html, ClassStyle, IncludeBackground,
 };
-use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
 use tera::escape_html;
+use tree_sitter_highlight::{HighlightConfiguration, Highlighter as TSHighlighter, HtmlRenderer};
+
+use crate::codeblock::grammars::{find_tree_sitter_grammar, TREE_SITTER_SCOPES};
 
 /// Not public, but from syntect::html
 fn write_css_color(s: &mut String, c: Color) {
@@ -91,102 +94,200 @@ impl<'config> InlineHighlighter<'config> {
     }
 }
 
-pub(crate) enum SyntaxHighlighter<'config> {
-    Inlined(InlineHighlighter<'config>),
-    Classed(ClassHighlighter<'config>),
-    /// We might not want highlighting but we want line numbers or to hide some lines
-    NoHighlight,
+/// A syntax highlighting backend, called once per code block
+pub(crate) trait Highlighter {
+    /// Returns the HTML of every line given, the hidden lines of the code block being
+    /// already removed. Lines include their line ending.
+    fn highlight(&mut self, lines: &[&str]) -> Vec<String>;
+
+    /// Anything to add after the last line, e.g. tags that are still open
+    fn finalize(&mut self) -> Option<String> {
+        None
+    }
+
+    /// The style of the `<pre>`, for inline themes
+    fn pre_style(&self) -> Option<String> {
+        None
+    }
+
+    /// The class of the `<pre>`, for CSS themes
+    fn pre_class(&self) -> Option<String> {
+        None
+    }
+
+    /// The style of the highlighted lines, for inline themes
+    fn mark_style(&self) -> Option<String> {
+        None
+    }
 }
 
-impl<'config> SyntaxHighlighter<'config> {
-    pub fn new(highlight_code: bool, s: SyntaxAndTheme<'config>) -> Self {
-        if highlight_code {
-            if let Some(theme) = s.theme {
-                SyntaxHighlighter::Inlined(InlineHighlighter::new(s.syntax, s.syntax_set, theme))
-            } else {
-                SyntaxHighlighter::Classed(ClassHighlighter::new(s.syntax, s.syntax_set))
-            }
-        } else {
-            SyntaxHighlighter::NoHighlight
-        }
+/// The class set on `<pre>` when highlighting with classes
+fn code_class() -> Option<String> {
+    if let ClassStyle::SpacedPrefixed { prefix } = CLASS_STYLE {
+        Some(format!("{}code", prefix))
+    } else {
+        unreachable!()
     }
+}
 
-    pub fn highlight_line(&mut self, line: &str) -> String {
-        use SyntaxHighlighter::*;
+/// Inline themes need to set the background/foreground colour on <pre>
+fn theme_pre_style(theme: &Theme) -> String {
+    let mut styles = String::from("background-color:");
+    write_css_color(&mut styles, theme.settings.background.unwrap_or(Color::WHITE));
+    styles.push_str(";color:");
+    write_css_color(&mut styles, theme.settings.foreground.unwrap_or(Color::BLACK));
+    styles.push(';');
+    styles
+}
 
-        match self {
-            Inlined(h) => h.highlight_line(line),
-            Classed(h) => h.highlight_line(line),
-            NoHighlight => escape_html(line),
-        }
+/// Inline themes need to set the background colour of highlighted lines
+fn theme_mark_style(theme: &Theme) -> String {
+    let mut styles = String::from("background-color:");
+    write_css_color(
+        &mut styles,
+        theme.settings.line_highlight.unwrap_or(Color { r: 255, g: 255, b: 0, a: 0 }),
+    );
+    styles.push(';');
+    styles
+}
+
+impl<'config> Highlighter for InlineHighlighter<'config> {
+    fn highlight(&mut self, lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| self.highlight_line(line)).collect()
     }
 
-    pub fn finalize(&mut self) -> Option<String> {
-        use SyntaxHighlighter::*;
+    fn pre_style(&self) -> Option<String> {
+        Some(theme_pre_style(self.theme))
+    }
 
-        match self {
-            Inlined(_) | NoHighlight => None,
-            Classed(h) => Some(h.finalize()),
-        }
+    fn mark_style(&self) -> Option<String> {
+        Some(theme_mark_style(self.theme))
     }
+}
 
-    /// Inlined needs to set the background/foreground colour on <pre>
-    pub fn pre_style(&self) -> Option<String> {
-        use SyntaxHighlighter::*;
-
-        match self {
-            Classed(_) | NoHighlight => None,
-            Inlined(h) => {
-                let mut styles = String::from("background-color:");
-                write_css_color(&mut styles, h.theme.settings.background.unwrap_or(Color::WHITE));
-                styles.push_str(";color:");
-                write_css_color(&mut styles, h.theme.settings.foreground.unwrap_or(Color::BLACK));
-                styles.push(';');
-                Some(styles)
-            }
-        }
+impl<'config> Highlighter for ClassHighlighter<'config> {
+    fn highlight(&mut self, lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| self.highlight_line(line)).collect()
     }
 
-    /// Classed needs to set a class on the pre
-    pub fn pre_class(&self) -> Option<String> {
-        use SyntaxHighlighter::*;
+    fn finalize(&mut self) -> Option<String> {
+        Some(ClassHighlighter::finalize(self))
+    }
+
+    fn pre_class(&self) -> Option<String> {
+        code_class()
+    }
+}
 
-        match self {
-            Classed(_) => {
-                if let ClassStyle::SpacedPrefixed { prefix } = CLASS_STYLE {
-                    Some(format!("{}code", prefix))
-                } else {
-                    unreachable!()
+/// We might not want highlighting but we want line numbers or to hide some lines
+pub(crate) struct NoHighlighter;
+
+impl Highlighter for NoHighlighter {
+    fn highlight(&mut self, lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| escape_html(line)).collect()
+    }
+}
+
+/// Highlights a whole code block with one of the bundled tree-sitter grammars.
+/// The captures are styled as their TextMate scopes, either with classes or with the theme colours
+pub(crate) struct TreeSitterHighlighter<'config> {
+    config: &'static HighlightConfiguration,
+    theme: Option<&'config Theme>,
+    /// The HTML attributes of every capture in `TREE_SITTER_SCOPES`
+    attributes: Vec<String>,
+}
+
+impl<'config> TreeSitterHighlighter<'config> {
+    pub fn new(config: &'static HighlightConfiguration, theme: Option<&'config Theme>) -> Self {
+        let theme_highlighter = theme.map(syntect::highlighting::Highlighter::new);
+        let attributes = TREE_SITTER_SCOPES
+            .iter()
+            .map(|(_, scope)| match theme_highlighter {
+                Some(ref highlighter) => {
+                    let style = highlighter.style_for_stack(&[Scope::new(scope).unwrap()]);
+                    let mut attribute = String::from("style=\"color:");
+                    write_css_color(&mut attribute, style.foreground);
+                    attribute.push(';');
+                    if style.font_style.contains(FontStyle::BOLD) {
+                        attribute.push_str("font-weight:bold;");
+                    }
+                    if style.font_style.contains(FontStyle::ITALIC) {
+                        attribute.push_str("font-style:italic;");
+                    }
+                    attribute.push('"');
+                    attribute
                 }
-            }
-            Inlined(_) | NoHighlight => None,
+                None => {
+                    let classes: Vec<_> = scope.split('.').map(|s| format!("z-{}", s)).collect();
+                    format!("class=\"{}\"", classes.join(" "))
+                }
+            })
+            .collect();
+        Self { config, theme, attributes }
+    }
+}
+
+impl<'config> Highlighter for TreeSitterHighlighter<'config> {
+    // The closure is needed so the injection callback doesn't require a `'static` highlighter
+    #[allow(clippy::redundant_closure)]
+    fn highlight(&mut self, lines: &[&str]) -> Vec<String> {
+        let code = lines.concat();
+        let mut highlighter = TSHighlighter::new();
+        let mut renderer = HtmlRenderer::new();
+        let highlighted = highlighter
+            .highlight(self.config, code.as_bytes(), None, |lang| find_tree_sitter_grammar(lang))
+            .and_then(|events| {
+                renderer.render(events, code.as_bytes(), &|h| self.attributes[h.0].as_bytes())
+            });
+
+        match highlighted {
+            Ok(()) => renderer.lines().map(|line| line.to_owned()).collect(),
+            // The parser got cancelled or an injected language failed: show the code as is
+            Err(_) => lines.iter().map(|line| escape_html(line)).collect(),
         }
     }
 
-    /// Inlined needs to set the background/foreground colour
-    pub fn mark_style(&self) -> Option<String> {
-        use SyntaxHighlighter::*;
-
-        match self {
-            Classed(_) | NoHighlight => None,
-            Inlined(h) => {
-                let mut styles = String::from("background-color:");
-                write_css_color(
-                    &mut styles,
-                    h.theme.settings.line_highlight.unwrap_or(Color { r: 255, g: 255, b: 0, a: 0 }),
-                );
-                styles.push(';');
-                Some(styles)
-            }
+    fn pre_style(&self) -> Option<String> {
+        self.theme.map(theme_pre_style)
+    }
+
+    fn pre_class(&self) -> Option<String> {
+        if self.theme.is_some() {
+            None
+        } else {
+            code_class()
         }
     }
+
+    fn mark_style(&self) -> Option<String> {
+        self.theme.map(theme_mark_style)
+    }
+}
+
+/// Picks the highlighter of a code block from the resolved syntax and theme, and the
+/// tree-sitter grammar if the language uses that backend
+pub(crate) fn get_highlighter<'config>(
+    highlight_code: bool,
+    s: SyntaxAndTheme<'config>,
+    tree_sitter: Option<&'static HighlightConfiguration>,
+) -> Box<dyn Highlighter + 'config> {
+    if !highlight_code {
+        return Box::new(NoHighlighter);
+    }
+
+    match (tree_sitter, s.theme) {
+        (Some(config), theme) => Box::new(TreeSitterHighlighter::new(config, theme)),
+        (None, Some(theme)) => Box::new(InlineHighlighter::new(s.syntax, s.syntax_set, theme)),
+        (None, None) => Box::new(ClassHighlighter::new(s.syntax, s.syntax_set)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codeblock::grammars::resolve_tree_sitter_grammar;
     use config::highlighting::resolve_syntax_and_theme;
-    use config::Config;
+    use config::{Config, HighlightBackend};
     use syntect::util::LinesWithEndings;
 
     #[test]
@@ -228,17 +329,54 @@ mod tests {
         assert!(out.ends_with("</span>"));
     }
 
+    #[test]
+    fn can_highlight_with_tree_sitter() {
+        let mut config = Config::default();
+        config.markdown.highlight_code = true;
+        config.markdown.highlighter = HighlightBackend::TreeSitter;
+        let code = "fn main() {\n    let s = \"<b>\";\n}\n";
+        let grammar = resolve_tree_sitter_grammar(Some("rust"), &config);
+        let mut highlighter = TreeSitterHighlighter::new(grammar.unwrap(), None);
+        let lines: Vec<_> = LinesWithEndings::from(code).collect();
+        let out = highlighter.highlight(&lines);
+
+        assert_eq!(out.len(), 3);
+        assert!(out[0].starts_with(r#"<span class="z-keyword">fn</span> "#));
+        assert!(out[0].contains(r#"<span class="z-entity z-name z-function">main</span>"#));
+        assert!(out[1].contains(r#"<span class="z-string">&quot;&lt;b&gt;&quot;</span>"#));
+        assert_eq!(highlighter.pre_class(), Some("z-code".to_owned()));
+    }
+
+    #[test]
+    fn can_highlight_with_tree_sitter_and_theme() {
+        let mut config = Config::default();
+        config.markdown.highlight_code = true;
+        config.markdown.language_highlighters.insert("rs".to_owned(), HighlightBackend::TreeSitter);
+        let syntax_and_theme = resolve_syntax_and_theme(Some("rs"), &config);
+        let grammar = resolve_tree_sitter_grammar(Some("rs"), &config);
+        let mut highlighter = get_highlighter(true, syntax_and_theme, grammar);
+        let out = highlighter.highlight(&["fn main() {}\n"]).concat();
+
+        assert!(out.starts_with(r#"<span style="color:#b48ead;">fn</span>"#));
+        assert!(highlighter.pre_style().is_some());
+    }
+
+    #[test]
+    fn falls_back_to_syntect_without_tree_sitter_grammar() {
+        let mut config = Config::default();
+        config.markdown.highlighter = HighlightBackend::TreeSitter;
+        assert!(resolve_tree_sitter_grammar(Some("py"), &config).is_none());
+    }
+
     #[test]
     fn no_highlight_escapes_html() {
         let mut config = Config::default();
         config.markdown.highlight_code = false;
         let code = "<script>alert('hello')</script>";
         let syntax_and_theme = resolve_syntax_and_theme(Some("py"), &config);
-        let mut highlighter = SyntaxHighlighter::new(false, syntax_and_theme);
-        let mut out = String::new();
-        for line in LinesWithEndings::from(code) {
-            out.push_str(&highlighter.highlight_line(line));
-        }
+        let mut highlighter = get_highlighter(false, syntax_and_theme, None);
+        let lines: Vec<_> = LinesWithEndings::from(code).collect();
+        let out = highlighter.highlight(&lines).concat();
         assert!(!out.contains("<script>"));
     }
 }
@@ -0,0 +1,95 @@
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use errors::{bail, Error, Result};
+use utils::fs::{is_path_in_directory, read_file};
+
+/// If the line is an `ANCHOR: name` or `ANCHOR_END: name` region marker, returns whether
+/// it is an end marker and the name of the region
+fn parse_region_marker(line: &str) -> Option<(bool, &str)> {
+    let (is_end, rest) = if let Some(idx) = line.find("ANCHOR_END:") {
+        (true, &line[idx + "ANCHOR_END:".len()..])
+    } else if let Some(idx) = line.find("ANCHOR:") {
+        (false, &line[idx + "ANCHOR:".len()..])
+    } else {
+        return None;
+    };
+    let rest = rest.trim_start();
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'));
+    Some((is_end, &rest[..end.unwrap_or(rest.len())]))
+}
+
+/// Only keeps the lines between the `ANCHOR: name` and `ANCHOR_END: name` markers
+fn extract_region<'a>(lines: &[&'a str], region: &str) -> Option<Vec<&'a str>> {
+    let mut in_region = false;
+    let mut found = false;
+    let mut res = Vec::new();
+
+    for line in lines {
+        match parse_region_marker(line) {
+            Some((false, name)) if name == region => {
+                in_region = true;
+                found = true;
+            }
+            Some((true, name)) if name == region => in_region = false,
+            _ if in_region => res.push(*line),
+            _ => (),
+        }
+    }
+
+    if found {
+        Some(res)
+    } else {
+        None
+    }
+}
+
+/// Reads the file given to the `include` option of a code block, relative to the site directory.
+/// Returns the full path of the file, for `zola serve` to watch, and the code to highlight:
+/// only the `region` and/or the `lines` asked for if any, without the region markers.
+/// The `lines` are counted from the start of the region if there is one.
+pub fn read_included_code(
+    base_path: &Path,
+    include: &str,
+    lines: Option<RangeInclusive<usize>>,
+    region: Option<&str>,
+) -> Result<(PathBuf, String)> {
+    let full_path = base_path.join(include);
+    if !full_path.exists() {
+        bail!("Included file `{}` doesn't exist", include);
+    }
+    if !is_path_in_directory(base_path, &full_path)? {
+        bail!("Included file `{}` is outside of the site directory", include);
+    }
+    let content = read_file(&full_path)
+        .map_err(|e| Error::chain(format!("Failed to read included file `{}`", include), e))?;
+
+    let all_lines: Vec<_> = content.lines().collect();
+    let mut code_lines = match region {
+        Some(region) => match extract_region(&all_lines, region) {
+            Some(l) => l,
+            None => bail!("Region `{}` not found in included file `{}`", region, include),
+        },
+        None => all_lines,
+    };
+
+    if let Some(lines) = lines {
+        let (start, end) = (*lines.start(), *lines.end());
+        if start == 0 || start > code_lines.len() {
+            bail!(
+                "Lines `{}-{}` are out of bounds of included file `{}` ({} lines)",
+                start,
+                end,
+                include,
+                code_lines.len()
+            );
+        }
+        code_lines = code_lines[start - 1..end.min(code_lines.len())].to_vec();
+    }
+
+    code_lines.retain(|l| parse_region_marker(l).is_none());
+
+    let mut code = code_lines.join("\n");
+    code.push('\n');
+    Ok((full_path, code))
+}
@@ -1,17 +1,26 @@
 mod fence;
+mod grammars;
 mod highlight;
+mod include;
+mod processor;
 
 use std::ops::RangeInclusive;
 
+use pulldown_cmark::escape::escape_html;
 use syntect::util::LinesWithEndings;
 
-use crate::codeblock::highlight::SyntaxHighlighter;
+use crate::codeblock::grammars::resolve_tree_sitter_grammar;
+use crate::codeblock::highlight::{get_highlighter, Highlighter};
 use config::highlighting::{resolve_syntax_and_theme, HighlightSource};
 use config::Config;
 pub(crate) use fence::FenceSettings;
+pub use grammars::check_language_highlighters;
+pub(crate) use include::read_included_code;
+pub(crate) use processor::run_fence_processor;
 
 fn opening_html(
     language: Option<&str>,
+    name: Option<&str>,
     pre_style: Option<String>,
     pre_class: Option<String>,
     line_numbers: bool,
@@ -20,6 +29,11 @@ fn opening_html(
     if line_numbers {
         html.push_str(" data-linenos");
     }
+    if let Some(name) = name {
+        html.push_str(" data-name=\"");
+        escape_html(&mut html, name).expect("Could not write to buffer");
+        html.push('"');
+    }
     let mut classes = String::new();
 
     if let Some(lang) = language {
@@ -60,13 +74,24 @@ fn opening_html(
     html
 }
 
+/// The `<figcaption>` shown above code blocks with a `title` or `name`
+fn caption_html(caption: &str) -> String {
+    let mut html = String::from("<figure class=\"code-block\"><figcaption>");
+    escape_html(&mut html, caption).expect("Could not write to buffer");
+    html.push_str("</figcaption>");
+    html
+}
+
 pub struct CodeBlock<'config> {
-    highlighter: SyntaxHighlighter<'config>,
+    highlighter: Box<dyn Highlighter + 'config>,
     // fence options
     line_numbers: bool,
     line_number_start: usize,
     highlight_lines: Vec<RangeInclusive<usize>>,
     hide_lines: Vec<RangeInclusive<usize>>,
+    /// The prefix of the `id` of every line, e.g. `code-3` for `#code-3-L12`
+    line_anchors_prefix: Option<String>,
+    has_caption: bool,
 }
 
 impl<'config> CodeBlock<'config> {
@@ -75,9 +100,13 @@ impl<'config> CodeBlock<'config> {
         config: &'config Config,
         // path to the current file if there is one, to point where the error is
         path: Option<&'config str>,
+        // 1-indexed position of the code block in the current file, used for the line anchors
+        index: usize,
     ) -> (Self, String) {
         let syntax_and_theme = resolve_syntax_and_theme(fence.language, config);
-        if syntax_and_theme.source == HighlightSource::NotFound {
+        let tree_sitter = resolve_tree_sitter_grammar(fence.language, config);
+        // The tree-sitter grammars are built-in as well
+        if syntax_and_theme.source == HighlightSource::NotFound && tree_sitter.is_none() {
             let lang = fence.language.unwrap();
             if let Some(p) = path {
                 eprintln!("Warning: Highlight language {} not found in {}", lang, p);
@@ -85,14 +114,19 @@ impl<'config> CodeBlock<'config> {
                 eprintln!("Warning: Highlight language {} not found", lang);
             }
         }
-        let highlighter = SyntaxHighlighter::new(config.markdown.highlight_code, syntax_and_theme);
+        let highlighter =
+            get_highlighter(config.markdown.highlight_code, syntax_and_theme, tree_sitter);
 
-        let html_start = opening_html(
+        let caption = fence.title.or(fence.name);
+        let mut html_start = caption.map(caption_html).unwrap_or_default();
+        html_start.push_str(&opening_html(
             fence.language,
+            fence.name,
             highlighter.pre_style(),
             highlighter.pre_class(),
             fence.line_numbers,
-        );
+        ));
+        let line_anchors = fence.line_anchors || config.markdown.code_line_anchors;
         (
             Self {
                 highlighter,
@@ -100,11 +134,25 @@ impl<'config> CodeBlock<'config> {
                 line_number_start: fence.line_number_start,
                 highlight_lines: fence.highlight_lines,
                 hide_lines: fence.hide_lines,
+                line_anchors_prefix: if fence.line_numbers && line_anchors {
+                    Some(format!("code-{}", index))
+                } else {
+                    None
+                },
+                has_caption: caption.is_some(),
             },
             html_start,
         )
     }
 
+    pub fn closing_html(&self) -> &'static str {
+        if self.has_caption {
+            "</code></pre></figure>\n"
+        } else {
+            "</code></pre>\n"
+        }
+    }
+
     pub fn highlight(&mut self, content: &str) -> String {
         let mut buffer = String::new();
         let mark_style = self.highlighter.mark_style();
@@ -114,19 +162,16 @@ impl<'config> CodeBlock<'config> {
         }
 
         // syntect leaking here in this file
-        for (i, line) in LinesWithEndings::from(content).enumerate() {
+        // first do we need to skip some lines?
+        let lines: Vec<_> = LinesWithEndings::from(content)
+            .enumerate()
+            .filter(|(i, _)| !self.hide_lines.iter().any(|range| range.contains(&(i + 1))))
+            .collect();
+        let visible_lines: Vec<_> = lines.iter().map(|(_, line)| *line).collect();
+        let highlighted_lines = self.highlighter.highlight(&visible_lines);
+
+        for ((i, _), highlighted_line) in lines.into_iter().zip(highlighted_lines) {
             let one_indexed = i + 1;
-            // first do we need to skip that line?
-            let mut skip = false;
-            for range in &self.hide_lines {
-                if range.contains(&one_indexed) {
-                    skip = true;
-                    break;
-                }
-            }
-            if skip {
-                continue;
-            }
 
             // Next is it supposed to be higlighted?
             let mut is_higlighted = false;
@@ -137,8 +182,15 @@ impl<'config> CodeBlock<'config> {
             }
 
             if self.line_numbers {
-                buffer.push_str("<tr><td>");
                 let num = format!("{}", self.line_number_start + i);
+                let num = if let Some(ref prefix) = self.line_anchors_prefix {
+                    let id = format!("{}-L{}", prefix, num);
+                    buffer.push_str(&format!("<tr id=\"{}\"><td>", id));
+                    format!("<a href=\"#{}\">{}</a>", id, num)
+                } else {
+                    buffer.push_str("<tr><td>");
+                    num
+                };
                 if is_higlighted {
                     buffer.push_str("<mark");
                     if let Some(ref s) = mark_style {
@@ -156,7 +208,6 @@ impl<'config> CodeBlock<'config> {
                 buffer.push_str("</td><td>");
             }
 
-            let highlighted_line = self.highlighter.highlight_line(line);
             if is_higlighted {
                 buffer.push_str("<mark");
                 if let Some(ref s) = mark_style {
@@ -0,0 +1,99 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use sha2::{Digest, Sha256};
+
+use config::FenceProcessor;
+use errors::{bail, Error, Result};
+use utils::fs::{create_directory, create_file, read_file};
+
+/// Makes the names of the files being written to the cache unique, pages being rendered in parallel
+static CACHE_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// The SHA-256 of the command, its arguments and the code, each prefixed by its length so
+/// moving text from one to another changes the key
+fn get_cache_key(processor: &FenceProcessor, code: &str) -> String {
+    let mut hasher = Sha256::new();
+    let parts = std::iter::once(&processor.command).chain(&processor.args).map(|s| s.as_str());
+    for part in parts.chain(std::iter::once(code)) {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn run_command(processor: &FenceProcessor, code: &str, cwd: Option<&Path>) -> Result<String> {
+    let mut command = Command::new(&processor.command);
+    command
+        .args(&processor.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| Error::chain(format!("Failed to run `{}`", processor.command), e))?;
+
+    // Written from another thread so a command writing a lot before reading all its input
+    // doesn't deadlock
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = code.to_owned();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| Error::chain(format!("Failed to run `{}`", processor.command), e))?;
+    // The command might not read its input at all, that's its business
+    let _ = writer.join();
+
+    if !output.status.success() {
+        bail!(
+            "`{}` exited with {}: {}",
+            processor.command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|e| Error::chain(format!("`{}` didn't output UTF-8", processor.command), e))
+}
+
+/// Keeps the output of a command in the cache directory. The file is written under another
+/// name first so a page rendered at the same time never reads it half written
+fn store_output(cache_dir: &Path, cache_path: &Path, output: &str) -> Result<()> {
+    let count = CACHE_WRITES.fetch_add(1, Ordering::Relaxed);
+    let tmp_path = cache_path.with_extension(format!("{}-{}.tmp", std::process::id(), count));
+    create_directory(cache_dir)?;
+    create_file(&tmp_path, output)?;
+    fs::rename(&tmp_path, cache_path)
+        .map_err(|e| Error::chain(format!("Failed to write {}", cache_path.display()), e))
+}
+
+/// Runs the command of a fence processor on the code of a block and returns its output.
+/// If there is a `cache_dir`, the outputs are kept there by hash of the command and the code
+/// so an unchanged block is not processed again, in this build or in the next ones.
+/// The command runs in the site directory if there is one.
+pub fn run_fence_processor(
+    processor: &FenceProcessor,
+    code: &str,
+    cwd: Option<&Path>,
+    cache_dir: Option<&Path>,
+) -> Result<String> {
+    let cache_dir = match cache_dir {
+        Some(dir) => dir,
+        None => return run_command(processor, code, cwd),
+    };
+    let cache_path = cache_dir.join(format!("{}.html", get_cache_key(processor, code)));
+    if let Ok(output) = read_file(&cache_path) {
+        return Ok(output);
+    }
+
+    let output = run_command(processor, code, cwd)?;
+    store_output(cache_dir, &cache_path, &output)?;
+    Ok(output)
+}
@@ -1,9 +1,12 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
 
 use config::Config;
 use front_matter::InsertAnchor;
 use tera::{Context, Tera};
+use utils::site::WikiLinkTargets;
 use utils::templates::ShortcodeDefinition;
 
 /// All the information from the zola site that is needed to render HTML from markdown
@@ -18,6 +21,16 @@ pub struct RenderContext<'a> {
     pub insert_anchor: InsertAnchor,
     pub lang: &'a str,
     pub shortcode_definitions: Cow<'a, HashMap<String, ShortcodeDefinition>>,
+    pub imageproc: Option<&'a Mutex<imageproc::Processor>>,
+    /// The range of heading levels included in the table of contents
+    pub toc_min_level: u32,
+    pub toc_max_level: u32,
+    /// The site directory, code blocks can only include files from there
+    pub base_path: Option<&'a Path>,
+    /// The pages the `[[wiki links]]` can point to
+    pub wiki_links: Cow<'a, WikiLinkTargets>,
+    /// The number of lines before the content in its file, for the line numbers in errors
+    pub line_offset: usize,
 }
 
 impl<'a> RenderContext<'a> {
@@ -43,6 +56,12 @@ impl<'a> RenderContext<'a> {
             config,
             lang,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            imageproc: None,
+            toc_min_level: config.markdown.toc_min_level,
+            toc_max_level: config.markdown.toc_max_level,
+            base_path: None,
+            wiki_links: Cow::Owned(HashMap::new()),
+            line_offset: 0,
         }
     }
 
@@ -57,6 +76,36 @@ impl<'a> RenderContext<'a> {
         self.current_page_path = Some(path);
     }
 
+    /// Same as above, the front matter values override the ones from the config
+    pub fn set_toc_levels(&mut self, min_level: Option<u32>, max_level: Option<u32>) {
+        if let Some(min_level) = min_level {
+            self.toc_min_level = min_level;
+        }
+        if let Some(max_level) = max_level {
+            self.toc_max_level = max_level;
+        }
+    }
+
+    /// Same as above, only needed for `markdown.responsive_images`
+    pub fn set_image_processor(&mut self, imageproc: &'a Mutex<imageproc::Processor>) {
+        self.imageproc = Some(imageproc);
+    }
+
+    /// Same as above, only needed for code blocks using `include`
+    pub fn set_base_path(&mut self, base_path: &'a Path) {
+        self.base_path = Some(base_path);
+    }
+
+    /// Same as above, only needed for `markdown.wiki_links`
+    pub fn set_wiki_links(&mut self, wiki_links: &'a WikiLinkTargets) {
+        self.wiki_links = Cow::Borrowed(wiki_links);
+    }
+
+    /// Same as above, the content of pages and sections comes after their front matter
+    pub fn set_line_offset(&mut self, line_offset: usize) {
+        self.line_offset = line_offset;
+    }
+
     // In use in the markdown filter
     // NOTE: This RenderContext is not i18n-aware, see MarkdownFilter::filter for details
     // If this function is ever used outside of MarkdownFilter, take this into consideration
@@ -71,6 +120,12 @@ impl<'a> RenderContext<'a> {
             config,
             lang: &config.default_language,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            imageproc: None,
+            toc_min_level: config.markdown.toc_min_level,
+            toc_max_level: config.markdown.toc_max_level,
+            base_path: None,
+            wiki_links: Cow::Owned(HashMap::new()),
+            line_offset: 0,
         }
     }
 }
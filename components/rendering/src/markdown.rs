@@ -1,21 +1,31 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
 use lazy_static::lazy_static;
 use pulldown_cmark as cmark;
+use serde_derive::Serialize;
 
 use crate::context::RenderContext;
 use crate::table_of_contents::{make_table_of_contents, Heading};
-use config::SectionTagsMode;
+use config::{ResponsiveImages, SectionTagsMode};
 use errors::{Error, Result};
 use front_matter::InsertAnchor;
-use utils::site::resolve_internal_link;
+use utils::fs::is_path_in_directory;
+use utils::site::{resolve_internal_link, resolve_wiki_link};
 use utils::slugs::slugify_anchors;
 use utils::vec::InsertMany;
 
 use self::cmark::{Event, LinkType, Options, Parser, Tag};
-use crate::codeblock::{CodeBlock, FenceSettings};
+use crate::codeblock::{read_included_code, run_fence_processor, CodeBlock, FenceSettings};
 use crate::shortcode::{Shortcode, SHORTCODE_PLACEHOLDER};
+use crate::source::Source;
 
 const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
+const TOC_MARKER: &str = "<!-- toc -->";
 const ANCHOR_LINK_TEMPLATE: &str = "anchor-link.html";
+const ADMONITION_TEMPLATE: &str = "admonition.html";
+const RESPONSIVE_IMAGE_TEMPLATE: &str = "responsive-image.html";
+const TOC_TEMPLATE: &str = "toc.html";
 
 #[derive(Debug)]
 pub struct Rendered {
@@ -26,6 +36,8 @@ pub struct Rendered {
     pub internal_links: Vec<(String, Option<String>)>,
     /// Outgoing links to external webpages (i.e. HTTP(S) targets).
     pub external_links: Vec<String>,
+    /// Files included in code blocks, watched by `zola serve`
+    pub included_files: Vec<PathBuf>,
 }
 
 /// Tracks a heading in a slice of pulldown-cmark events
@@ -102,6 +114,8 @@ fn fix_link(
             } else {
                 link.to_string()
             }
+        } else if let Some(fingerprinted) = context.config.fingerprint.fingerprint_link(link) {
+            fingerprinted
         } else {
             link.to_string()
         }
@@ -239,10 +253,324 @@ fn make_flat_sections(events : &mut Vec<Event>) -> () {
     }
 }
 
+/// Finds the index of the `End(Tag::BlockQuote)` matching the blockquote starting at `start_idx`
+fn find_blockquote_end(events: &[Event], start_idx: usize) -> usize {
+    let mut depth = 0;
+    for (i, event) in events.iter().enumerate().skip(start_idx) {
+        match event {
+            Event::Start(Tag::BlockQuote) => depth += 1,
+            Event::End(Tag::BlockQuote) => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => (),
+        }
+    }
+    events.len() - 1
+}
+
+/// Checks whether the blockquote `events` (without its own start/end events) begins with a
+/// GitHub-style admonition marker like `[!NOTE]` or `[!WARNING] Custom title`.
+/// Returns the lowercased type, the optional title and the events making up the body.
+fn parse_admonition<'a>(events: &[Event<'a>]) -> Option<(String, Option<String>, Vec<Event<'a>>)> {
+    if events.first() != Some(&Event::Start(Tag::Paragraph)) {
+        return None;
+    }
+
+    // The marker has to be on the first line of the first paragraph
+    let line_end = events.iter().position(|e| {
+        matches!(e, Event::SoftBreak | Event::HardBreak | Event::End(Tag::Paragraph))
+    })?;
+    let line = get_text(&events[1..line_end]);
+    let rest = line.strip_prefix("[!")?;
+    let marker_end = rest.find(']')?;
+    let kind = &rest[..marker_end];
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    let title = rest[marker_end + 1..].trim();
+    let title = if title.is_empty() { None } else { Some(title.to_owned()) };
+
+    let mut body = Vec::with_capacity(events.len() - line_end);
+    if events[line_end] != Event::End(Tag::Paragraph) {
+        // The first paragraph continues after the marker line
+        body.push(Event::Start(Tag::Paragraph));
+    }
+    body.extend(events[line_end + 1..].iter().cloned());
+
+    Some((kind.to_lowercase(), title, body))
+}
+
+/// Replaces the blockquotes starting with an admonition marker by the rendered
+/// `admonition.html` template. Admonitions can be nested.
+fn render_admonitions<'a>(
+    events: Vec<Event<'a>>,
+    context: &RenderContext,
+) -> Result<Vec<Event<'a>>> {
+    let mut res = Vec::with_capacity(events.len());
+    let mut i = 0;
+
+    while i < events.len() {
+        if events[i] != Event::Start(Tag::BlockQuote) {
+            res.push(events[i].clone());
+            i += 1;
+            continue;
+        }
+
+        let end_idx = find_blockquote_end(&events, i);
+        let (kind, title, body_events) = match parse_admonition(&events[i + 1..end_idx]) {
+            Some(admonition) => admonition,
+            None => {
+                res.push(events[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut body = String::new();
+        cmark::html::push_html(&mut body, render_admonitions(body_events, context)?.into_iter());
+
+        let mut c = tera::Context::new();
+        c.insert("type", &kind);
+        c.insert("title", &title);
+        c.insert("body", &body);
+        c.insert("lang", &context.lang);
+
+        let admonition =
+            utils::templates::render_template(ADMONITION_TEMPLATE, &context.tera, c, &None)
+                .map_err(|e| Error::chain("Failed to render admonition template", e))?;
+        res.push(Event::Html(admonition.into()));
+        i = end_idx + 1;
+    }
+
+    Ok(res)
+}
+
+/// Pushes the `text` events to `res`, with their `[[target]]` and `[[target|label]]` wiki links
+/// replaced by links to the pages they resolve to
+fn push_text_with_wiki_links<'a>(
+    text: &str,
+    res: &mut Vec<Event<'a>>,
+    context: &RenderContext,
+    internal_links: &mut Vec<(String, Option<String>)>,
+) -> Result<()> {
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let inner_len = match rest[start + 2..].find("]]") {
+            Some(len) if len > 0 && !rest[start + 2..start + 2 + len].contains('[') => len,
+            _ => {
+                res.push(Event::Text(rest[..start + 2].to_owned().into()));
+                rest = &rest[start + 2..];
+                continue;
+            }
+        };
+        let inner = &rest[start + 2..start + 2 + inner_len];
+        let (target, label) = match inner.find('|') {
+            Some(idx) => (&inner[..idx], inner[idx + 1..].trim()),
+            None => (inner, inner.trim()),
+        };
+
+        let resolved =
+            resolve_wiki_link(target, context.lang, &context.wiki_links, &context.permalinks)?;
+        internal_links.push((resolved.md_path, resolved.anchor));
+        let link = Tag::Link(LinkType::Inline, resolved.permalink.into(), "".into());
+        if start > 0 {
+            res.push(Event::Text(rest[..start].to_owned().into()));
+        }
+        res.push(Event::Start(link.clone()));
+        res.push(Event::Text(label.to_owned().into()));
+        res.push(Event::End(link));
+        rest = &rest[start + 2 + inner_len + 2..];
+    }
+
+    if !rest.is_empty() {
+        res.push(Event::Text(rest.to_owned().into()));
+    }
+    Ok(())
+}
+
+/// Replaces the wiki links found in the text outside of links and code by regular links.
+/// The text can be split over several events, e.g. `[[a]]` is parsed as 5 text events.
+fn render_wiki_links<'a>(
+    events: Vec<Event<'a>>,
+    context: &RenderContext,
+    internal_links: &mut Vec<(String, Option<String>)>,
+) -> Result<Vec<Event<'a>>> {
+    let mut res = Vec::with_capacity(events.len());
+    let mut text = String::new();
+    let mut link_depth = 0;
+
+    for event in events {
+        match event {
+            Event::Text(ref t) if link_depth == 0 => {
+                text.push_str(t);
+                continue;
+            }
+            Event::Start(Tag::Link(..)) | Event::Start(Tag::Image(..)) => link_depth += 1,
+            Event::End(Tag::Link(..)) | Event::End(Tag::Image(..)) => link_depth -= 1,
+            _ => (),
+        }
+        if !text.is_empty() {
+            let text = std::mem::take(&mut text);
+            push_text_with_wiki_links(&text, &mut res, context, internal_links)?;
+        }
+        res.push(event);
+    }
+    if !text.is_empty() {
+        push_text_with_wiki_links(&text, &mut res, context, internal_links)?;
+    }
+
+    Ok(res)
+}
+
+/// One `<source>` of a responsive image
+#[derive(Debug, Serialize)]
+struct ImageSource {
+    #[serde(rename = "type")]
+    mime: &'static str,
+    srcset: String,
+}
+
+/// Finds the file a local image points to: paths starting with `/` are looked up in the
+/// `static` and `content` directories, other ones are relative to the current page.
+/// Returns `None` for remote images or if the file doesn't exist.
+fn find_local_image(
+    src: &str,
+    base_path: &Path,
+    current_page_path: Option<&str>,
+) -> Option<PathBuf> {
+    // Remote images and data URLs
+    if src.starts_with("//") || src.contains(':') {
+        return None;
+    }
+    // Query strings and fragments are not part of the file name
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+
+    let candidates = if let Some(src) = src.strip_prefix('/') {
+        vec![base_path.join("static").join(src), base_path.join("content").join(src)]
+    } else {
+        let page_dir = Path::new(current_page_path?).parent()?;
+        vec![base_path.join("content").join(page_dir).join(src)]
+    };
+
+    candidates
+        .into_iter()
+        .find(|p| p.is_file() && is_path_in_directory(base_path, p).unwrap_or(false))
+}
+
+fn image_mime_type(url: &str) -> &'static str {
+    match url.rsplit('.').next() {
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+/// Replaces the local images by the `responsive-image.html` template, with the image resized
+/// to every width and format set in `markdown.responsive_images`
+fn render_responsive_images<'a>(
+    events: Vec<Event<'a>>,
+    context: &RenderContext,
+    responsive_images: &ResponsiveImages,
+    imageproc: &Mutex<imageproc::Processor>,
+) -> Result<Vec<Event<'a>>> {
+    let mut res = Vec::with_capacity(events.len());
+    let mut i = 0;
+
+    while i < events.len() {
+        let (src, title) = match &events[i] {
+            Event::Start(Tag::Image(_, src, title)) => (src, title),
+            _ => {
+                res.push(events[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut imageproc = imageproc.lock().expect("Couldn't lock imageproc (responsive images)");
+        let image_path = find_local_image(src, imageproc.base_path(), context.current_page_path)
+            .filter(|p| imageproc::Format::is_lossy(p).is_some());
+        let image_path = match image_path {
+            Some(p) => p,
+            None => {
+                res.push(events[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let end_idx = i + events[i..]
+            .iter()
+            .position(|e| matches!(e, Event::End(Tag::Image(..))))
+            .expect("Image end before start?");
+        let alt = get_text(&events[i + 1..end_idx]);
+
+        let meta = imageproc::read_image_metadata(&image_path)?;
+        let unified_path = image_path
+            .strip_prefix(imageproc.base_path())
+            .unwrap_or(&image_path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        // We never upscale images
+        let mut widths: Vec<_> =
+            responsive_images.widths.iter().copied().filter(|w| *w < meta.width).collect();
+        if widths.is_empty() {
+            widths.push(meta.width);
+        }
+
+        let mut sources = Vec::with_capacity(responsive_images.formats.len());
+        for format in &responsive_images.formats {
+            let mut srcset = Vec::with_capacity(widths.len());
+            let mut mime = "";
+            for width in &widths {
+                let resized = imageproc.enqueue(
+                    unified_path.clone(),
+                    image_path.clone(),
+                    "fit_width",
+                    Some(*width),
+                    None,
+                    format,
+                    responsive_images.quality,
+                )?;
+                mime = image_mime_type(&resized.url);
+                srcset.push(format!("{} {}w", resized.url, resized.width));
+            }
+            sources.push(ImageSource { mime, srcset: srcset.join(", ") });
+        }
+
+        let src =
+            context.config.fingerprint.fingerprint_link(src).unwrap_or_else(|| src.to_string());
+        let mut escaped_src = String::new();
+        cmark::escape::escape_href(&mut escaped_src, &src).expect("Could not write to buffer");
+
+        let mut c = tera::Context::new();
+        c.insert("src", &escaped_src);
+        c.insert("alt", &alt);
+        c.insert("title", &title.as_ref());
+        c.insert("width", &meta.width);
+        c.insert("height", &meta.height);
+        c.insert("sizes", &responsive_images.sizes);
+        c.insert("sources", &sources);
+        c.insert("lang", &context.lang);
+
+        let image =
+            utils::templates::render_template(RESPONSIVE_IMAGE_TEMPLATE, &context.tera, c, &None)
+                .map_err(|e| Error::chain("Failed to render responsive image template", e))?;
+        res.push(Event::Html(image.into()));
+        i = end_idx + 1;
+    }
+
+    Ok(res)
+}
+
 pub fn markdown_to_html(
     content: &str,
     context: &RenderContext,
     html_shortcodes: Vec<Shortcode>,
+    source: &Source,
 ) -> Result<Rendered> {
     lazy_static! {
         static ref EMOJI_REPLACER: gh_emoji::Replacer = gh_emoji::Replacer::new();
@@ -259,9 +587,19 @@ pub fn markdown_to_html(
     let mut error = None;
 
     let mut code_block: Option<CodeBlock> = None;
+    let mut code_block_count = 0;
+    // The code is highlighted all at once at the end of the block, the text can come in
+    // several events, e.g. in a blockquote
+    let mut code_block_content = String::new();
+    // Whether the current code block content comes from an included file, ignoring its body
+    let mut code_block_included = false;
+    // Set instead of `code_block` if the code block is rendered by a command
+    let mut fence_processor = None;
+    let mut included_files = Vec::new();
 
     let mut inserted_anchors: Vec<String> = vec![];
     let mut headings: Vec<Heading> = vec![];
+    let toc;
     let mut internal_links = Vec::new();
     let mut external_links = Vec::new();
 
@@ -269,6 +607,7 @@ pub fn markdown_to_html(
 
     let mut opts = Options::empty();
     let mut has_summary = false;
+    let mut has_toc_marker = false;
     opts.insert(Options::ENABLE_TABLES);
     opts.insert(Options::ENABLE_FOOTNOTES);
     opts.insert(Options::ENABLE_STRIKETHROUGH);
@@ -289,9 +628,10 @@ pub fn markdown_to_html(
         for (event, mut range) in Parser::new_ext(content, opts).into_offset_iter() {
             match event {
                 Event::Text(text) => {
-                    if let Some(ref mut code_block) = code_block {
-                        let html = code_block.highlight(&text);
-                        events.push(Event::Html(html.into()));
+                    if code_block.is_some() || fence_processor.is_some() {
+                        if !code_block_included {
+                            code_block_content.push_str(&text);
+                        }
                     } else {
                         let text = if context.config.markdown.render_emoji {
                             EMOJI_REPLACER.replace_all(&text).to_string().into()
@@ -320,7 +660,11 @@ pub fn markdown_to_html(
 
                                     let shortcode = next_shortcode.take().unwrap();
 
-                                    match shortcode.render(&context.tera, &context.tera_context) {
+                                    match shortcode.render(
+                                        &context.tera,
+                                        &context.tera_context,
+                                        source,
+                                    ) {
                                         Ok(s) => {
                                             events.push(Event::Html(s.into()));
                                             new_text = new_text[(sc_span.end - range.start)..]
@@ -350,14 +694,80 @@ pub fn markdown_to_html(
                         cmark::CodeBlockKind::Fenced(fence_info) => FenceSettings::new(fence_info),
                         _ => FenceSettings::new(""),
                     };
-                    let (block, begin) = CodeBlock::new(fence, context.config, path);
-                    code_block = Some(block);
-                    events.push(Event::Html(begin.into()));
+                    let included = fence.include.map(|include| {
+                        let base_path = context.base_path.ok_or_else(|| {
+                            Error::msg("Code blocks can only include files in pages and sections")
+                        })?;
+                        read_included_code(
+                            base_path,
+                            include,
+                            fence.include_lines.clone(),
+                            fence.include_region,
+                        )
+                    });
+                    match included {
+                        Some(Ok((included_path, code))) => {
+                            code_block_content = code;
+                            included_files.push(included_path);
+                            code_block_included = true;
+                        }
+                        Some(Err(e)) => {
+                            error = Some(e);
+                            code_block_included = true;
+                        }
+                        None => (),
+                    }
+                    let processor = fence.language.and_then(|lang| {
+                        context.config.markdown.fence_processors.get(lang).map(|p| (lang, p))
+                    });
+                    if let Some((lang, processor)) = processor {
+                        fence_processor = Some((lang.to_owned(), processor));
+                    } else {
+                        code_block_count += 1;
+                        let (block, begin) =
+                            CodeBlock::new(fence, context.config, path, code_block_count);
+                        events.push(Event::Html(begin.into()));
+                        code_block = Some(block);
+                    }
                 }
                 Event::End(Tag::CodeBlock(_)) => {
-                    // reset highlight and close the code block
-                    code_block = None;
-                    events.push(Event::Html("</code></pre>\n".into()));
+                    code_block_included = false;
+                    let code = std::mem::take(&mut code_block_content);
+                    if let Some((lang, processor)) = fence_processor.take() {
+                        // the command output replaces the whole code block
+                        let cache_dir = match (
+                            context.base_path,
+                            &context.config.markdown.fence_processors_cache_dir,
+                        ) {
+                            (Some(base_path), Some(dir)) => Some(base_path.join(dir)),
+                            _ => None,
+                        };
+                        match run_fence_processor(
+                            processor,
+                            &code,
+                            context.base_path,
+                            cache_dir.as_deref(),
+                        ) {
+                            Ok(html) => events.push(Event::Html(html.into())),
+                            Err(e) => {
+                                error = Some(Error::chain(
+                                    format!(
+                                        "Failed to process a `{}` code block in {}",
+                                        lang,
+                                        path.unwrap_or("the content")
+                                    ),
+                                    e,
+                                ));
+                            }
+                        }
+                    } else {
+                        // highlight and close the code block
+                        let mut block = code_block.take().expect("Code block end before start?");
+                        if !code.is_empty() {
+                            events.push(Event::Html(block.highlight(&code).into()));
+                        }
+                        events.push(Event::Html(block.closing_html().into()));
+                    }
                 }
                 Event::Start(Tag::Link(link_type, link, title)) if link.is_empty() => {
                     error = Some(Error::msg("There is a link that is missing a URL"));
@@ -431,6 +841,12 @@ pub fn markdown_to_html(
                         events.push(Event::Html(CONTINUE_READING.into()));
                         continue;
                     }
+                    if text.contains(TOC_MARKER) {
+                        // Replaced by the rendered table of contents once we have all the headings
+                        has_toc_marker = true;
+                        events.push(Event::Html(TOC_MARKER.into()));
+                        continue;
+                    }
                     if !contains_shortcode(text.as_ref()) {
                         events.push(Event::Html(text));
                         continue;
@@ -448,7 +864,8 @@ pub fn markdown_to_html(
                                 }
 
                                 let shortcode = next_shortcode.take().unwrap();
-                                match shortcode.render(&context.tera, &context.tera_context) {
+                                match shortcode.render(&context.tera, &context.tera_context, source)
+                                {
                                     Ok(s) => {
                                         events.push(Event::Html(s.into()));
                                         new_text = new_text[(sc_span.end - range.start)..]
@@ -484,6 +901,10 @@ pub fn markdown_to_html(
             })
             .collect();
 
+        if context.config.markdown.wiki_links {
+            events = render_wiki_links(events, context, &mut internal_links)?;
+        }
+
         // If user wants page sections wrapped in <section> tags, we do this before managing
         // headings. This way we don't interfere with the code that builds an index of Heading
         // locations.
@@ -558,6 +979,11 @@ pub fn markdown_to_html(
             }
 
             // record heading to make table of contents
+            if heading_ref.level < context.toc_min_level
+                || heading_ref.level > context.toc_max_level
+            {
+                continue;
+            }
             let permalink = format!("{}#{}", context.current_page_permalink, id);
             let h =
                 Heading { level: heading_ref.level, id, permalink, title, children: Vec::new() };
@@ -568,6 +994,50 @@ pub fn markdown_to_html(
             events.insert_many(anchors_to_insert);
         }
 
+        toc = make_table_of_contents(headings);
+
+        if has_toc_marker {
+            let mut c = tera::Context::new();
+            c.insert("toc", &toc);
+            c.insert("lang", &context.lang);
+
+            let rendered_toc =
+                utils::templates::render_template(TOC_TEMPLATE, &context.tera, c, &None)
+                    .map_err(|e| Error::chain("Failed to render table of contents template", e))?;
+            for event in events.iter_mut() {
+                if matches!(event, Event::Html(text) if text.as_ref() == TOC_MARKER) {
+                    *event = Event::Html(rendered_toc.clone().into());
+                }
+            }
+        }
+
+        if let (Some(responsive_images), Some(imageproc)) =
+            (&context.config.markdown.responsive_images, context.imageproc)
+        {
+            if !responsive_images.widths.is_empty() {
+                events = render_responsive_images(events, context, responsive_images, imageproc)?;
+            }
+        }
+
+        // After the responsive images as they need the original paths to find the files
+        if !context.config.fingerprint.manifest.is_empty() {
+            for event in events.iter_mut() {
+                if let Event::Start(Tag::Image(link_type, src, title)) = event {
+                    if let Some(fingerprinted) = context.config.fingerprint.fingerprint_link(src) {
+                        *event = Event::Start(Tag::Image(
+                            *link_type,
+                            fingerprinted.into(),
+                            title.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if context.config.markdown.render_admonitions {
+            events = render_admonitions(events, context)?;
+        }
+
         cmark::html::push_html(&mut html, events.into_iter());
     }
 
@@ -577,9 +1047,10 @@ pub fn markdown_to_html(
         Ok(Rendered {
             summary_len: if has_summary { html.find(CONTINUE_READING) } else { None },
             body: html,
-            toc: make_table_of_contents(headings),
+            toc,
             internal_links,
             external_links,
+            included_files,
         })
     }
 }
@@ -3,26 +3,39 @@ use std::collections::HashMap;
 use errors::{Error, Result};
 use utils::templates::{ShortcodeDefinition, ShortcodeFileType};
 
+use crate::source::Source;
+
 mod parser;
 
 pub(crate) use parser::{parse_for_shortcodes, Shortcode, SHORTCODE_PLACEHOLDER};
 
 /// Extracts the shortcodes present in the source, check if we know them and errors otherwise
 pub fn extract_shortcodes(
-    source: &str,
+    source: &Source,
     definitions: &HashMap<String, ShortcodeDefinition>,
 ) -> Result<(String, Vec<Shortcode>)> {
     let (out, mut shortcodes) = parse_for_shortcodes(source)?;
+    set_tera_names(&mut shortcodes, definitions, source)?;
+
+    Ok((out, shortcodes))
+}
 
-    for sc in &mut shortcodes {
+/// Sets the template name of the shortcodes and of the ones nested in their bodies
+fn set_tera_names(
+    shortcodes: &mut [Shortcode],
+    definitions: &HashMap<String, ShortcodeDefinition>,
+    source: &Source,
+) -> Result<()> {
+    for sc in shortcodes {
         if let Some(def) = definitions.get(&sc.name) {
             sc.tera_name = def.tera_name.clone();
         } else {
-            return Err(Error::msg(format!("Found usage of a shortcode named `{}` but we do not know about. Make sure it's not a typo and that a field name `{}.{{html,md}} exists in the `templates/shortcodes` directory.", sc.name, sc.name)));
+            return Err(Error::msg(format!("Found usage of a shortcode named `{}` but we do not know about. Make sure it's not a typo and that a field name `{}.{{html,md}} exists in the `templates/shortcodes` directory. It is used {}", sc.name, sc.name, source.locate(&sc.source_span))));
         }
+        set_tera_names(&mut sc.nested, definitions, source)?;
     }
 
-    Ok((out, shortcodes))
+    Ok(())
 }
 
 pub fn insert_md_shortcodes(
@@ -30,6 +43,7 @@ pub fn insert_md_shortcodes(
     shortcodes: Vec<Shortcode>,
     tera_context: &tera::Context,
     tera: &tera::Tera,
+    source: &Source,
 ) -> Result<(String, Vec<Shortcode>)> {
     // (span, len transformed)
     let mut transforms = Vec::new();
@@ -46,7 +60,7 @@ pub fn insert_md_shortcodes(
         }
 
         let span = sc.span.clone();
-        let res = sc.render(tera, tera_context)?;
+        let res = sc.render(tera, tera_context, source)?;
         transforms.push((span.clone(), res.len()));
         content.replace_range(span, &res);
     }
@@ -76,21 +90,26 @@ mod tests {
                         name: "a".to_string(),
                         args: to_value(&HashMap::<u8, u8>::new()).unwrap(),
                         span: 0..SHORTCODE_PLACEHOLDER.len(),
+                        source_span: 0..0,
                         body: None,
                         nth: 1,
                         tera_name: "shortcodes/a.md".to_owned(),
+                        nested: Vec::new(),
                     },
                     Shortcode {
                         name: "a".to_string(),
                         args: to_value(&HashMap::<u8, u8>::new()).unwrap(),
                         span: SHORTCODE_PLACEHOLDER.len()..(2 * SHORTCODE_PLACEHOLDER.len()),
+                        source_span: 0..0,
                         body: None,
                         nth: 2,
                         tera_name: "shortcodes/a.md".to_owned(),
+                        nested: Vec::new(),
                     }
                 ],
                 &tera_context,
-                &tera
+                &tera,
+                &Source::new("", 0),
             )
             .unwrap()
             .0,
@@ -104,17 +123,42 @@ mod tests {
                     name: "bodied".to_string(),
                     args: to_value(&HashMap::<u8, u8>::new()).unwrap(),
                     span: 9..(9 + SHORTCODE_PLACEHOLDER.len()),
+                    source_span: 0..0,
                     body: Some("Content of the body".to_owned()),
                     nth: 1,
 
                     tera_name: "shortcodes/bodied.md".to_owned(),
+                    nested: Vec::new(),
                 },],
                 &tera_context,
-                &tera
+                &tera,
+                &Source::new("", 0),
             )
             .unwrap()
             .0,
             "Much wow Content of the body".to_string()
         );
     }
+
+    #[test]
+    fn shortcode_errors_point_to_their_location() {
+        let mut tera = templates::ZOLA_TERA.clone();
+        tera.add_raw_template("shortcodes/outer.md", "{{ body }}").unwrap();
+        tera.add_raw_template("shortcodes/inner.html", "{{ missing }}").unwrap();
+        let definitions = utils::templates::get_shortcodes(&tera);
+
+        let source = Source::new("Hello\n\n{% outer() %}\nSome {{ inner() }}\n{% end %}", 3);
+        let (content, shortcodes) = extract_shortcodes(&source, &definitions).unwrap();
+        let err = insert_md_shortcodes(content, shortcodes, &tera::Context::new(), &tera, &source)
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "Failed to render inner shortcode at line 7, column 6:\n  |\n6 | {% outer() %}\n7 | Some {{ inner() }}\n  |      ^^^^^^^^^^^^^"
+        );
+
+        let source = Source::new("{{ unknown() }}", 0);
+        let err = extract_shortcodes(&source, &definitions).unwrap_err();
+        assert!(format!("{}", err)
+            .ends_with("at line 1, column 1:\n  |\n1 | {{ unknown() }}\n  | ^^^^^^^^^^^^^^^"));
+    }
 }
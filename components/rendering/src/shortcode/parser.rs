@@ -1,6 +1,7 @@
 use std::ops::Range;
 
-use errors::{bail, Result};
+use errors::{bail, Error, Result};
+use pest::error::{ErrorVariant, InputLocation};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
@@ -8,6 +9,8 @@ use std::collections::HashMap;
 use tera::{to_value, Context, Map, Tera, Value};
 use utils::templates::ShortcodeFileType;
 
+use crate::source::Source;
+
 pub const SHORTCODE_PLACEHOLDER: &str = "||ZOLA_SC_PLACEHOLDER||";
 
 #[derive(PartialEq, Debug)]
@@ -15,10 +18,14 @@ pub struct Shortcode {
     pub(crate) name: String,
     pub(crate) args: Value,
     pub(crate) span: Range<usize>,
+    /// Where the shortcode is in the content of the page, to locate the errors
+    pub(crate) source_span: Range<usize>,
     pub(crate) body: Option<String>,
     pub(crate) nth: usize,
     // set later down the line, for quick access without needing the definitions
     pub(crate) tera_name: String,
+    /// The shortcodes found in the body, replaced by placeholders in it
+    pub(crate) nested: Vec<Shortcode>,
 }
 
 impl Shortcode {
@@ -30,12 +37,18 @@ impl Shortcode {
         }
     }
 
-    pub fn render(self, tera: &Tera, context: &Context) -> Result<String> {
+    pub fn render(self, tera: &Tera, context: &Context, source: &Source) -> Result<String> {
         let name = self.name;
         let tpl_name = self.tera_name;
+        let source_span = self.source_span;
         let mut new_context = Context::from_value(self.args)?;
 
-        if let Some(body_content) = self.body {
+        if let Some(mut body_content) = self.body {
+            // The nested shortcodes are rendered first, from the last one so the spans stay valid
+            for sc in self.nested.into_iter().rev() {
+                let span = sc.span.clone();
+                body_content.replace_range(span, &sc.render(tera, context, source)?);
+            }
             // Trimming right to avoid most shortcodes with bodies ending up with a HTML new line
             new_context.insert("body", body_content.trim_end());
         }
@@ -43,7 +56,10 @@ impl Shortcode {
         new_context.extend(context.clone());
 
         let res = utils::templates::render_template(&tpl_name, tera, new_context, &None)
-            .map_err(|e| errors::Error::chain(format!("Failed to render {} shortcode", name), e))?
+            .map_err(|e| {
+                let location = source.locate(&source_span);
+                Error::chain(format!("Failed to render {} shortcode {}", name, location), e)
+            })?
             .replace("\r\n", "\n");
 
         Ok(res)
@@ -119,16 +135,23 @@ fn parse_kwarg_value(pair: Pair<Rule>) -> Value {
     val.unwrap()
 }
 
-/// Returns (shortcode_name, kwargs)
-fn parse_shortcode_call(pair: Pair<Rule>) -> (String, Value) {
+/// Returns (shortcode_name, kwargs), the positional arguments being in the `args` kwarg.
+/// `source_span` is the span of the whole shortcode in the source, to locate errors
+fn parse_shortcode_call(
+    pair: Pair<Rule>,
+    source: &Source,
+    source_span: &Range<usize>,
+) -> Result<(String, Value)> {
     let mut name = None;
     let mut args = Map::new();
+    let mut positional_args = Vec::new();
 
     for p in pair.into_inner() {
         match p.as_rule() {
             Rule::ident => {
                 name = Some(p.as_span().as_str().to_string());
             }
+            Rule::literal => positional_args.push(parse_kwarg_value(p)),
             Rule::kwarg => {
                 let mut arg_name = None;
                 let mut arg_val = None;
@@ -149,17 +172,39 @@ fn parse_shortcode_call(pair: Pair<Rule>) -> (String, Value) {
             _ => unreachable!("Got something unexpected in a shortcode: {:?}", p),
         }
     }
-    (name.unwrap(), Value::Object(args))
+    let name = name.unwrap();
+    if !positional_args.is_empty() {
+        if args.contains_key("args") {
+            bail!(
+                "Shortcode `{}` has positional arguments, which are given to it as `args`, so it can't have an `args` keyword argument {}",
+                name,
+                source.locate(source_span)
+            );
+        }
+        args.insert("args".to_string(), Value::Array(positional_args));
+    }
+    Ok((name, Value::Object(args)))
+}
+
+fn get_invocation_count(nths: &mut HashMap<String, usize>, name: &str) -> usize {
+    let nth = nths.entry(String::from(name)).or_insert(0);
+    *nth += 1;
+    *nth
+}
+
+pub fn parse_for_shortcodes(source: &Source) -> Result<(String, Vec<Shortcode>)> {
+    parse_content(source.content, 0, source, &mut HashMap::new())
 }
 
-pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
+/// Parses the content of a page or the body of a shortcode starting at `offset` in the source,
+/// the invocation counts being shared by the nested shortcodes
+fn parse_content(
+    content: &str,
+    offset: usize,
+    source: &Source,
+    nths: &mut HashMap<String, usize>,
+) -> Result<(String, Vec<Shortcode>)> {
     let mut shortcodes = Vec::new();
-    let mut nths = HashMap::new();
-    let mut get_invocation_count = |name: &str| {
-        let nth = nths.entry(String::from(name)).or_insert(0);
-        *nth += 1;
-        *nth
-    };
     let mut output = String::with_capacity(content.len());
 
     let mut pairs = match ContentParser::parse(Rule::page, content) {
@@ -185,6 +230,7 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
                 Rule::boolean => "a boolean (true, false)".to_string(),
                 Rule::all_chars => "a alphanumerical character".to_string(),
                 Rule::kwargs => "a list of keyword arguments".to_string(),
+                Rule::sc_args => "a list of arguments".to_string(),
                 Rule::sc_def => "a shortcode definition".to_string(),
                 Rule::shortcode_with_body => "a shortcode with body".to_string(),
                 Rule::ignored_shortcode_with_body => "an ignored shortcode with body".to_string(),
@@ -196,7 +242,14 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
                 Rule::page => "a page".to_string(),
                 Rule::WHITESPACE => "whitespace".to_string(),
             });
-            bail!("{}", fancy_e);
+            let pos = match fancy_e.location {
+                InputLocation::Pos(pos) | InputLocation::Span((pos, _)) => offset + pos,
+            };
+            let message = match fancy_e.variant {
+                ErrorVariant::CustomError { message } => message,
+                ErrorVariant::ParsingError { .. } => "unknown parsing error".to_string(),
+            };
+            bail!("Failed to parse the shortcodes: {} {}", message, source.locate(&(pos..pos)));
         }
     };
 
@@ -206,33 +259,45 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
             Rule::text => output.push_str(p.as_span().as_str()),
             Rule::inline_shortcode => {
                 let start = output.len();
-                let (name, args) = parse_shortcode_call(p);
-                let nth = get_invocation_count(&name);
+                let source_span = (offset + p.as_span().start())..(offset + p.as_span().end());
+                let (name, args) = parse_shortcode_call(p, source, &source_span)?;
+                let nth = get_invocation_count(nths, &name);
                 shortcodes.push(Shortcode {
                     name,
                     args,
                     span: start..(start + SHORTCODE_PLACEHOLDER.len()),
+                    source_span,
                     body: None,
                     nth,
                     tera_name: String::new(),
+                    nested: Vec::new(),
                 });
                 output.push_str(SHORTCODE_PLACEHOLDER);
             }
             Rule::shortcode_with_body => {
                 let start = output.len();
+                let source_span = (offset + p.as_span().start())..(offset + p.as_span().end());
                 let mut inner = p.into_inner();
                 // 3 items in inner: call, body, end
                 // we don't care about the closing tag
-                let (name, args) = parse_shortcode_call(inner.next().unwrap());
-                let body = inner.next().unwrap().as_span().as_str().trim();
-                let nth = get_invocation_count(&name);
+                let (name, args) =
+                    parse_shortcode_call(inner.next().unwrap(), source, &source_span)?;
+                let body_span = inner.next().unwrap().as_span();
+                let raw_body = body_span.as_str();
+                let body = raw_body.trim();
+                let leading_whitespace = raw_body.len() - raw_body.trim_start().len();
+                let body_offset = offset + body_span.start() + leading_whitespace;
+                let nth = get_invocation_count(nths, &name);
+                let (body, nested) = parse_content(body, body_offset, source, nths)?;
                 shortcodes.push(Shortcode {
                     name,
                     args,
                     span: start..(start + SHORTCODE_PLACEHOLDER.len()),
-                    body: Some(body.to_string()),
+                    source_span,
+                    body: Some(body),
                     nth,
                     tera_name: String::new(),
+                    nested,
                 });
                 output.push_str(SHORTCODE_PLACEHOLDER)
             }
@@ -269,6 +334,11 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
 mod tests {
     use super::*;
 
+    /// Parses content without front matter
+    fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
+        super::parse_for_shortcodes(&Source::new(content, 0))
+    }
+
     macro_rules! assert_lex_rule {
         ($rule: expr, $input: expr) => {
             let res = ContentParser::parse($rule, $input);
@@ -298,6 +368,8 @@ mod tests {
             "{{ youtube(id=1, autoplay=true, url='hey') }}",
             "{{ youtube(id=1, \nautoplay=true, url='hey', array=[]) }}",
             "{{ youtube(id=1, \nautoplay=true, url='hey', multi_aray=[[]]) }}",
+            "{{ youtube('hey', 1, [true]) }}",
+            "{{ youtube('hey', true_value=1) }}",
         ];
         for i in inputs {
             assert_lex_rule!(Rule::inline_shortcode, i);
@@ -372,9 +444,11 @@ mod tests {
             name: "a".to_string(),
             args: Value::Null,
             span: 10..20,
+            source_span: 0..0,
             body: None,
             nth: 0,
             tera_name: String::new(),
+            nested: Vec::new(),
         };
         // 6 -> 10 in length so +4 on both sides of the range
         sc.update_range(&(2..8), 10);
@@ -391,9 +465,11 @@ mod tests {
             name: "a".to_string(),
             args: Value::Null,
             span: 42..65,
+            source_span: 0..0,
             body: None,
             nth: 0,
             tera_name: String::new(),
+            nested: Vec::new(),
         };
         sc.update_range(&(9..32), 3);
         assert_eq!(sc.span, 22..45);
@@ -490,4 +566,60 @@ mod tests {
         .unwrap();
         assert_eq!(shortcodes.len(), 5);
     }
+
+    #[test]
+    fn can_extract_positional_args() {
+        let (_, shortcodes) =
+            parse_for_shortcodes("{{ hello('hey', false, 2.1, true_value=1) }}").unwrap();
+        assert_eq!(shortcodes[0].args.as_object().unwrap().len(), 2);
+        assert_eq!(
+            shortcodes[0].args["args"],
+            Value::Array(vec![
+                Value::String("hey".to_string()),
+                Value::Bool(false),
+                to_value(2.1).unwrap()
+            ])
+        );
+        assert_eq!(shortcodes[0].args["true_value"], to_value(1).unwrap());
+    }
+
+    #[test]
+    fn errors_on_positional_args_with_args_kwarg() {
+        let err = parse_for_shortcodes("Hi\n{{ hello(1, args='x') }}").unwrap_err();
+        let message = format!("{}", err);
+        assert!(message.contains("can't have an `args` keyword argument"));
+        assert!(message.contains("at line 2, column 1"));
+        // Without positional arguments, `args` is a keyword argument like the others
+        let (_, shortcodes) = parse_for_shortcodes("{{ hello(args='x') }}").unwrap();
+        assert_eq!(shortcodes[0].args["args"], Value::String("x".to_string()));
+    }
+
+    #[test]
+    fn doesnt_extract_positional_args_after_keyword_ones() {
+        let (out, shortcodes) = parse_for_shortcodes("{{ hello(a=1, 2) }}").unwrap();
+        assert_eq!(out, "{{ hello(a=1, 2) }}");
+        assert_eq!(shortcodes.len(), 0);
+    }
+
+    #[test]
+    fn can_extract_nested_shortcodes() {
+        let (out, shortcodes) = parse_for_shortcodes(
+            "{% tabs() %}{% tab(name='a') %}A {{ hello() }}{% end %}{% tab(name='b') %}B{% end %}{% end %} {{ hello() }}",
+        )
+        .unwrap();
+        assert_eq!(out, format!("{} {}", SHORTCODE_PLACEHOLDER, SHORTCODE_PLACEHOLDER));
+        assert_eq!(shortcodes.len(), 2);
+        let tabs = &shortcodes[0];
+        assert_eq!(tabs.body, Some(format!("{}{}", SHORTCODE_PLACEHOLDER, SHORTCODE_PLACEHOLDER)));
+        assert_eq!(tabs.nested.len(), 2);
+        assert_eq!(tabs.nested[0].body, Some(format!("A {}", SHORTCODE_PLACEHOLDER)));
+        assert_eq!(tabs.nested[0].nested[0].name, "hello");
+        assert_eq!(tabs.nested[1].nth, 2);
+        assert_eq!(
+            tabs.nested[1].span,
+            SHORTCODE_PLACEHOLDER.len()..(2 * SHORTCODE_PLACEHOLDER.len())
+        );
+        // the invocation count is shared with the nested shortcodes
+        assert_eq!(shortcodes[1].nth, 2);
+    }
 }
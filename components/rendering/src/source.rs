@@ -0,0 +1,101 @@
+use std::ops::Range;
+
+/// The markdown content of a page or section, used to point at the location of errors.
+/// The content comes after the front matter in the file so the line numbers are offset by it.
+#[derive(Debug, Clone, Copy)]
+pub struct Source<'a> {
+    pub content: &'a str,
+    /// The number of lines before the content in the file
+    pub line_offset: usize,
+}
+
+impl<'a> Source<'a> {
+    pub fn new(content: &'a str, line_offset: usize) -> Source<'a> {
+        Source { content, line_offset }
+    }
+
+    /// Returns the line and the column, both starting at 1, of the byte at `pos` in the file
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let before = &self.content[..pos];
+        let line = before.matches('\n').count() + 1 + self.line_offset;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// Returns where the span is, followed by an excerpt of the file with the start of the span
+    /// underlined, e.g.:
+    ///
+    /// ```text
+    /// at line 12, column 7:
+    ///    |
+    /// 11 | Some text
+    /// 12 | Hello {{ unknown() }}
+    ///    |       ^^^^^^^^^^^^^^^
+    /// ```
+    pub fn locate(&self, span: &Range<usize>) -> String {
+        let (line, column) = self.line_col(span.start);
+        let line_start = self.content[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = self.content[span.start..]
+            .find('\n')
+            .map(|i| span.start + i)
+            .unwrap_or(self.content.len());
+        let previous_line =
+            if line_start > 0 { self.content[..line_start - 1].rsplit('\n').next() } else { None };
+
+        let width = line.to_string().len();
+        let mut res =
+            format!("at line {}, column {}:\n{:width$} |\n", line, column, "", width = width);
+        let mut push_line = |number: usize, text: &str| {
+            let formatted = format!("{:>width$} | {}", number, text, width = width);
+            res.push_str(formatted.trim_end());
+            res.push('\n');
+        };
+        if let Some(previous_line) = previous_line {
+            push_line(line - 1, previous_line);
+        }
+        push_line(line, &self.content[line_start..line_end]);
+
+        // Keeping the tabs so the markers are aligned whatever their width
+        let padding: String = self.content[line_start..span.start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let markers = self.content[span.start..span.end.min(line_end)].chars().count().max(1);
+        res.push_str(&format!("{:width$} | {}{}", "", padding, "^".repeat(markers), width = width));
+        if span.end > line_end {
+            res.push_str(" ...");
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Source;
+
+    #[test]
+    fn can_get_line_and_column() {
+        let source = Source::new("Hello\n\twörld {{ a() }}", 3);
+        assert_eq!(source.line_col(0), (4, 1));
+        assert_eq!(source.line_col(6), (5, 1));
+        assert_eq!(source.line_col(14), (5, 8));
+    }
+
+    #[test]
+    fn can_locate_span() {
+        let source = Source::new("Hello\n\twörld {{ a() }}\nbye", 0);
+        assert_eq!(
+            source.locate(&(14..23)),
+            "at line 2, column 8:\n  |\n1 | Hello\n2 | \twörld {{ a() }}\n  | \t      ^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn can_locate_multiline_span_on_first_line() {
+        let source = Source::new("{% a() %}\nbody\n{% end %}", 9);
+        assert_eq!(
+            source.locate(&(0..24)),
+            "at line 10, column 1:\n   |\n10 | {% a() %}\n   | ^^^^^^^^^ ..."
+        );
+    }
+}
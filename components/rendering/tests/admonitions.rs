@@ -0,0 +1,77 @@
+use tera::Tera;
+
+use config::Config;
+use templates::ZOLA_TERA;
+
+mod common;
+
+use common::Renderer;
+
+fn render_admonitions(content: &str, tera: &Tera) -> String {
+    let mut config = Config::default_for_test();
+    config.markdown.render_admonitions = true;
+    Renderer::new().config(config).tera(tera.clone()).render(content).unwrap().body
+}
+
+#[test]
+fn can_render_admonition() {
+    let body = render_admonitions("> [!NOTE]\n> Some **important** text", &ZOLA_TERA);
+    assert_eq!(
+        body,
+        r#"<div class="admonition admonition-note">
+<p class="admonition-title">Note</p>
+<p>Some <strong>important</strong> text</p>
+</div>
+"#
+    );
+}
+
+#[test]
+fn can_render_admonition_with_title_and_blocks() {
+    let body = render_admonitions(
+        "> [!WARNING] Be careful\n>\n> - one\n> - two\n>\n> ```\n> code\n> ```",
+        &ZOLA_TERA,
+    );
+    assert!(body.starts_with(r#"<div class="admonition admonition-warning">"#));
+    assert!(body.contains(r#"<p class="admonition-title">Be careful</p>"#));
+    assert!(body.contains("<ul>\n<li>one</li>\n<li>two</li>\n</ul>"));
+    assert!(body.contains("<pre><code>code\n</code></pre>"));
+}
+
+#[test]
+fn can_render_nested_admonitions() {
+    let body = render_admonitions("> [!TIP]\n> outer\n>\n> > [!CAUTION]\n> > inner", &ZOLA_TERA);
+    assert!(body.starts_with(r#"<div class="admonition admonition-tip">"#));
+    assert!(body.contains(
+        r#"<div class="admonition admonition-caution">
+<p class="admonition-title">Caution</p>
+<p>inner</p>
+</div>"#
+    ));
+    assert!(!body.contains("blockquote"));
+}
+
+#[test]
+fn leaves_regular_blockquotes_alone() {
+    let body = render_admonitions("> Just a quote\n> [!NOTE] not a marker", &ZOLA_TERA);
+    assert_eq!(body, "<blockquote>\n<p>Just a quote\n[!NOTE] not a marker</p>\n</blockquote>\n");
+}
+
+#[test]
+fn can_override_admonition_template() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    tera.add_raw_template(
+        "admonition.html",
+        "<aside data-type=\"{{ type }}\">{{ body | safe }}</aside>",
+    )
+    .unwrap();
+    let body = render_admonitions("> [!Important]\n> hello", &tera);
+    assert_eq!(body, "<aside data-type=\"important\"><p>hello</p>\n</aside>");
+}
+
+#[test]
+fn doesnt_render_admonitions_when_disabled() {
+    let body = Renderer::new().tera(ZOLA_TERA.clone()).render("> [!NOTE]\n> hello").unwrap().body;
+    assert!(body.starts_with("<blockquote>"));
+}
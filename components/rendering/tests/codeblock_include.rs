@@ -0,0 +1,79 @@
+use rendering::Rendered;
+
+mod common;
+
+use common::{test_site_path, Renderer};
+
+fn render_with_includes(content: &str) -> errors::Result<Rendered> {
+    Renderer::new().base_path(test_site_path()).render(content)
+}
+
+#[test]
+fn can_include_file_in_code_block() {
+    let res = render_with_includes(
+        r#"
+```rust,include="examples/demo.rs"
+```
+    "#,
+    )
+    .unwrap();
+    assert_eq!(
+        res.body,
+        "<pre data-lang=\"rust\" class=\"language-rust \"><code class=\"language-rust\" data-lang=\"rust\">use std::collections::HashMap;\n\nfn main() {\n    let mut map = HashMap::new();\n    map.insert(&quot;hello&quot;, &quot;world&quot;);\n    println!(&quot;{:?}&quot;, map);\n}\n</code></pre>\n"
+    );
+    assert_eq!(res.included_files.len(), 1);
+    assert!(res.included_files[0].ends_with("examples/demo.rs"));
+}
+
+#[test]
+fn ignores_body_of_code_block_including_file() {
+    let res = render_with_includes(
+        r#"
+```include="examples/demo.rs", lines=1
+This is ignored
+```
+    "#,
+    )
+    .unwrap();
+    assert_eq!(res.body, "<pre><code>use std::collections::HashMap;\n</code></pre>\n");
+}
+
+#[test]
+fn can_include_lines_of_file() {
+    let res = render_with_includes(
+        r#"
+```include="examples/demo.rs", lines="5-6", hl_lines=2
+```
+    "#,
+    )
+    .unwrap();
+    assert_eq!(
+        res.body,
+        "<pre><code>    let mut map = HashMap::new();\n<mark>    map.insert(&quot;hello&quot;, &quot;world&quot;);\n</mark></code></pre>\n"
+    );
+}
+
+#[test]
+fn can_include_region_of_file() {
+    let res = render_with_includes(
+        r#"
+```include="examples/demo.rs", region=main, hide_lines=2-4
+```
+    "#,
+    )
+    .unwrap();
+    assert_eq!(res.body, "<pre><code>fn main() {\n}\n</code></pre>\n");
+}
+
+#[test]
+fn errors_on_missing_included_file_or_region() {
+    for fence in &[
+        r#"include="examples/missing.rs""#,
+        r#"include="examples/demo.rs", region=nope"#,
+        r#"include="examples/demo.rs", lines=100-120"#,
+        r#"include="../Cargo.toml""#,
+    ] {
+        let res = render_with_includes(&format!("```{}\n```", fence));
+        assert!(res.is_err(), "{}", fence);
+    }
+}
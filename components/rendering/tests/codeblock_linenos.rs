@@ -95,3 +95,36 @@ bar
         "<pre data-linenos style=\"background-color:#2b303b;color:#c0c5ce;\"><code><table><tbody><tr><td>1</td><td><span>foo\n</span><tr><td><mark style=\"background-color:#65737e30;\">2</mark></td><td><mark style=\"background-color:#65737e30;\"><span>bar\n</span></mark></tr></tbody></table></code></pre>\n"
     );
 }
+
+#[test]
+fn can_add_line_anchors() {
+    let tera_ctx = Tera::default();
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default_for_test();
+    config.markdown.highlight_code = true;
+    let context = RenderContext::new(
+        &tera_ctx,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    let res = render_content(
+        r#"
+```
+no anchors
+```
+
+```linenos, lineanchors, linenostart=12, hl_lines=2
+foo
+bar
+```
+    "#,
+        &context,
+    )
+    .unwrap();
+    assert!(res.body.ends_with(
+        "<pre data-linenos style=\"background-color:#2b303b;color:#c0c5ce;\"><code><table><tbody><tr id=\"code-2-L12\"><td><a href=\"#code-2-L12\">12</a></td><td><span>foo\n</span><tr id=\"code-2-L13\"><td><mark style=\"background-color:#65737e30;\"><a href=\"#code-2-L13\">13</a></mark></td><td><mark style=\"background-color:#65737e30;\"><span>bar\n</span></mark></tr></tbody></table></code></pre>\n"
+    ));
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use tera::Tera;
+
+use config::Config;
+use front_matter::InsertAnchor;
+use rendering::{render_content, RenderContext};
+
+#[test]
+fn can_add_name_to_code_block() {
+    let tera_ctx = Tera::default();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let context = RenderContext::new(
+        &tera_ctx,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    let res = render_content(
+        r#"
+```rust, name="src/main.rs"
+fn main() {}
+```
+    "#,
+        &context,
+    )
+    .unwrap();
+    assert_eq!(
+        res.body,
+        "<figure class=\"code-block\"><figcaption>src/main.rs</figcaption><pre data-name=\"src/main.rs\" data-lang=\"rust\" class=\"language-rust \"><code class=\"language-rust\" data-lang=\"rust\">fn main() {}\n</code></pre></figure>\n"
+    );
+}
+
+#[test]
+fn title_takes_precedence_over_name_in_caption() {
+    let tera_ctx = Tera::default();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let context = RenderContext::new(
+        &tera_ctx,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    let res = render_content(
+        r#"
+```name=config.toml, title="Your <config> file = ready"
+[markdown]
+```
+    "#,
+        &context,
+    )
+    .unwrap();
+    assert_eq!(
+        res.body,
+        "<figure class=\"code-block\"><figcaption>Your &lt;config&gt; file = ready</figcaption><pre data-name=\"config.toml\"><code>[markdown]\n</code></pre></figure>\n"
+    );
+}
+
+#[test]
+fn can_have_commas_in_quoted_title() {
+    let tera_ctx = Tera::default();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let context = RenderContext::new(
+        &tera_ctx,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    let res = render_content(
+        r#"
+```rust, title="Hello, world", linenos
+fn main() {}
+```
+    "#,
+        &context,
+    )
+    .unwrap();
+    assert!(res
+        .body
+        .starts_with("<figure class=\"code-block\"><figcaption>Hello, world</figcaption>"));
+    assert!(res.body.contains("data-lang=\"rust\""));
+    assert!(res.body.contains("<table>"));
+}
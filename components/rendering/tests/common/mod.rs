@@ -1,3 +1,17 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use config::Config;
+use errors::Result;
+use front_matter::InsertAnchor;
+use imageproc::Processor;
+use rendering::{render_content, RenderContext, Rendered};
+use tera::{Map, Tera, Value};
+use utils::site::WikiLinkTargets;
+
 pub struct ShortCode {
     pub name: &'static str,
     pub output: &'static str,
@@ -14,3 +28,112 @@ impl ShortCode {
         format!("{}.{}", self.name, if self.is_md { "md" } else { "html" })
     }
 }
+
+/// The `test_site` at the root of the repository, for the tests needing actual files
+pub fn test_site_path() -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "..", "..", "test_site"].iter().collect()
+}
+
+/// Renders content with a `RenderContext` using the default test config, with only the parts
+/// each test needs being set
+pub struct Renderer {
+    config: Config,
+    tera: Tera,
+    permalinks: HashMap<String, String>,
+    current_page_path: Option<String>,
+    base_path: Option<PathBuf>,
+    image_processor: bool,
+    wiki_links: WikiLinkTargets,
+    page: Option<Map<String, Value>>,
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer {
+            config: Config::default_for_test(),
+            tera: Tera::default(),
+            permalinks: HashMap::new(),
+            current_page_path: None,
+            base_path: None,
+            image_processor: false,
+            wiki_links: HashMap::new(),
+            page: None,
+        }
+    }
+
+    pub fn config(mut self, config: Config) -> Renderer {
+        self.config = config;
+        self
+    }
+
+    /// The shortcodes are the `shortcodes/` templates of that Tera instance
+    pub fn tera(mut self, tera: Tera) -> Renderer {
+        self.tera = tera;
+        self
+    }
+
+    pub fn permalinks(mut self, permalinks: HashMap<String, String>) -> Renderer {
+        self.permalinks = permalinks;
+        self
+    }
+
+    pub fn current_page_path(mut self, path: &str) -> Renderer {
+        self.current_page_path = Some(path.to_owned());
+        self
+    }
+
+    pub fn base_path(mut self, path: PathBuf) -> Renderer {
+        self.base_path = Some(path);
+        self
+    }
+
+    /// Processes images relative to the base path, which needs to be set
+    pub fn image_processor(mut self) -> Renderer {
+        self.image_processor = true;
+        self
+    }
+
+    pub fn wiki_links(mut self, wiki_links: WikiLinkTargets) -> Renderer {
+        self.wiki_links = wiki_links;
+        self
+    }
+
+    /// The `page` variable available to the shortcodes
+    pub fn page(mut self, page: Map<String, Value>) -> Renderer {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn render(&self, content: &str) -> Result<Rendered> {
+        let shortcode_def = utils::templates::get_shortcodes(&self.tera);
+        let imageproc = match self.base_path {
+            Some(ref base_path) if self.image_processor => {
+                Some(Mutex::new(Processor::new(base_path.clone(), &self.config)))
+            }
+            _ => None,
+        };
+        let mut context = RenderContext::new(
+            &self.tera,
+            &self.config,
+            &self.config.default_language,
+            "",
+            &self.permalinks,
+            InsertAnchor::None,
+        );
+        context.set_shortcode_definitions(&shortcode_def);
+        context.set_wiki_links(&self.wiki_links);
+        if let Some(ref path) = self.current_page_path {
+            context.set_current_page_path(path);
+        }
+        if let Some(ref base_path) = self.base_path {
+            context.set_base_path(base_path);
+        }
+        if let Some(ref imageproc) = imageproc {
+            context.set_image_processor(imageproc);
+        }
+        if let Some(ref page) = self.page {
+            context.tera_context.insert("page", page);
+        }
+        render_content(content, &context)
+    }
+}
@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use tempfile::tempdir;
+
+use config::{Config, FenceProcessor};
+
+mod common;
+
+use common::Renderer;
+
+fn render_with_processor(content: &str, lang: &str, command: &str, args: &[&str]) -> String {
+    render_in_site(content, lang, command, args, None, None)
+}
+
+fn render_in_site(
+    content: &str,
+    lang: &str,
+    command: &str,
+    args: &[&str],
+    base_path: Option<&Path>,
+    cache_dir: Option<&str>,
+) -> String {
+    let mut config = Config::default_for_test();
+    config.markdown.highlight_code = true;
+    config.markdown.fence_processors.insert(
+        lang.to_owned(),
+        FenceProcessor {
+            command: command.to_owned(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        },
+    );
+    config.markdown.fence_processors_cache_dir = cache_dir.map(|d| d.to_owned());
+    let mut renderer = Renderer::new().config(config);
+    if let Some(base_path) = base_path {
+        renderer = renderer.base_path(base_path.to_path_buf());
+    }
+    match renderer.render(content) {
+        Ok(res) => res.body,
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+#[test]
+fn can_render_code_block_with_command() {
+    let body = render_with_processor(
+        "Hello\n\n```svg\n<svg><circle r=\"1\"/></svg>\n```\n\n```rust\nfn main() {}\n```",
+        "svg",
+        "cat",
+        &[],
+    );
+    assert!(body.starts_with("<p>Hello</p>\n<svg><circle r=\"1\"/></svg>\n<pre"));
+    assert!(body.contains("<code class=\"language-rust\""));
+}
+
+#[test]
+fn passes_arguments_to_command() {
+    let body = render_with_processor(
+        "```upper\nhello\n```",
+        "upper",
+        "sh",
+        &["-c", "printf '<p>'; tr a-z A-Z; printf '</p>'"],
+    );
+    assert_eq!(body, "<p>HELLO\n</p>");
+}
+
+#[test]
+fn reports_failing_command() {
+    let body = render_with_processor(
+        "```fail\nhello\n```",
+        "fail",
+        "sh",
+        &["-c", "echo 'syntax error' >&2; exit 3"],
+    );
+    assert!(body.starts_with("Error: Failed to process a `fail` code block in the content"));
+
+    let body = render_with_processor("```nope\nhello\n```", "nope", "zola-command-not-found", &[]);
+    assert!(body.starts_with("Error: Failed to process a `nope` code block"));
+}
+
+#[test]
+fn caches_command_output_in_site_directory() {
+    let dir = tempdir().unwrap();
+    let args = ["-c", "echo run >> runs.txt; cat"];
+    let render =
+        |content| render_in_site(content, "count", "sh", &args, Some(dir.path()), Some(".cache"));
+
+    assert_eq!(render("```count\n<p>a</p>\n```"), "<p>a</p>\n");
+    assert_eq!(render("```count\n<p>a</p>\n```"), "<p>a</p>\n");
+    assert_eq!(render("```count\n<p>b</p>\n```"), "<p>b</p>\n");
+    let runs = std::fs::read_to_string(dir.path().join("runs.txt")).unwrap();
+    assert_eq!(runs.lines().count(), 2);
+    assert_eq!(std::fs::read_dir(dir.path().join(".cache")).unwrap().count(), 2);
+}
+
+#[test]
+fn doesnt_cache_command_output_by_default() {
+    let dir = tempdir().unwrap();
+    let args = ["-c", "echo run >> runs.txt; cat"];
+    let render = |content| render_in_site(content, "count", "sh", &args, Some(dir.path()), None);
+
+    assert_eq!(render("```count\n<p>a</p>\n```"), "<p>a</p>\n");
+    assert_eq!(render("```count\n<p>a</p>\n```"), "<p>a</p>\n");
+    let runs = std::fs::read_to_string(dir.path().join("runs.txt")).unwrap();
+    assert_eq!(runs.lines().count(), 2);
+    // Only the file written by the command
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
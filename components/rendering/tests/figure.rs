@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use config::Config;
+use imageproc::Processor;
+use templates::{global_fns, ZOLA_TERA};
+use tera::{Tera, Value};
+
+mod common;
+
+use common::{test_site_path, Renderer};
+
+fn render_figure(content: &str, config: &Config) -> errors::Result<String> {
+    render_figure_in_page(content, config, "http://a-website.com/posts/with-assets/")
+}
+
+/// Renders a figure in the page `posts/with-assets/index.md`, served at the given permalink
+fn render_figure_in_page(
+    content: &str,
+    config: &Config,
+    permalink: &str,
+) -> errors::Result<String> {
+    let base_path = test_site_path();
+    let output_path = base_path.join("public");
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    tera.register_function(
+        "resize_image",
+        global_fns::ResizeImage::new(
+            base_path.clone(),
+            Arc::new(Mutex::new(Processor::new(base_path.clone(), config))),
+            None,
+            output_path.clone(),
+        ),
+    );
+    tera.register_function(
+        "get_image_metadata",
+        global_fns::GetImageMetadata::new(base_path.clone(), None, output_path.clone()),
+    );
+    tera.register_function(
+        "get_url",
+        global_fns::GetUrl::new(base_path, config.clone(), HashMap::new(), output_path),
+    );
+
+    let mut page = tera::Map::new();
+    page.insert("relative_path".to_string(), Value::from("posts/with-assets/index.md"));
+    page.insert("permalink".to_string(), Value::from(permalink));
+    Renderer::new().config(config.clone()).tera(tera).page(page).render(content).map(|res| res.body)
+}
+
+#[test]
+fn can_render_figure_of_colocated_image() {
+    let config = Config::default_for_test();
+    let body = render_figure(
+        r#"{{ figure(src="zola.png", alt="The Zola logo", caption="Our logo", credit="Zola", widths=[128, 512]) }}"#,
+        &config,
+    )
+    .unwrap();
+    assert!(body.starts_with(
+        r#"<figure>
+<img src="http://a-website.com/posts/with-assets/zola.png" srcset="http://a-website.com/processed_images/"#
+    ));
+    assert!(body.contains(
+        r#" 128w, http://a-website.com/posts/with-assets/zola.png 256w" sizes="100vw" alt="The Zola logo" width="256" height="256">"#
+    ));
+    assert!(body.ends_with("\n<figcaption>Our logo <small>Zola</small></figcaption>\n</figure>\n"));
+}
+
+#[test]
+fn uses_page_permalink_for_colocated_image_url() {
+    let config = Config::default_for_test();
+    let body = render_figure_in_page(
+        r#"{{ figure(src="zola.png", alt="The Zola logo", widths=[128]) }}"#,
+        &config,
+        "http://a-website.com/blog/my-slug/",
+    )
+    .unwrap();
+    assert!(body.contains(r#"<img src="http://a-website.com/blog/my-slug/zola.png" srcset="#));
+    assert!(body.contains(" 128w, http://a-website.com/blog/my-slug/zola.png 256w"));
+    assert!(!body.contains("posts/with-assets"));
+}
+
+#[test]
+fn errors_on_missing_figure_image() {
+    let config = Config::default_for_test();
+    let res = render_figure(r#"{{ figure(src="missing.png", alt="Nothing") }}"#, &config);
+    assert!(res.is_err());
+}
+
+#[test]
+fn requires_figure_alt_text_in_check_mode() {
+    let mut config = Config::default_for_test();
+    assert!(render_figure(r#"{{ figure(src="zola.png") }}"#, &config).is_ok());
+
+    config.enable_check_mode();
+    let err = render_figure(r#"{{ figure(src="zola.png") }}"#, &config).unwrap_err();
+    let mut messages = format!("{}", err);
+    let mut source = std::error::Error::source(&err);
+    while let Some(e) = source {
+        messages.push_str(&format!("\n{}", e));
+        source = e.source();
+    }
+    assert!(messages.contains("requires an `alt` text"));
+}
@@ -60,3 +60,32 @@ fn can_detect_links() {
     let res = render_content("[abc](@/def/123.md)", HashMap::new());
     assert!(res.is_err());
 }
+
+#[test]
+fn can_link_to_fingerprinted_assets() {
+    let mut config = config::Config::default_for_test();
+    config
+        .fingerprint
+        .manifest
+        .insert("files/guide.pdf".to_owned(), "files/guide.0123456789abcdef.pdf".to_owned());
+    let tera = tera::Tera::default();
+    let permalinks = HashMap::new();
+    let context = rendering::RenderContext::new(
+        &tera,
+        &config,
+        &config.default_language,
+        "http://mypage.com",
+        &permalinks,
+        front_matter::InsertAnchor::None,
+    );
+
+    let rendered = rendering::render_content(
+        "[guide](/files/guide.pdf#page=2) ![cover](/files/guide.pdf) [other](/files/other.pdf)",
+        &context,
+    )
+    .unwrap();
+    assert_eq!(
+        rendered.body,
+        "<p><a href=\"/files/guide.0123456789abcdef.pdf#page=2\">guide</a> <img src=\"/files/guide.0123456789abcdef.pdf\" alt=\"cover\" /> <a href=\"/files/other.pdf\">other</a></p>\n"
+    );
+}
@@ -0,0 +1,61 @@
+use config::{Config, ResponsiveImages};
+use templates::ZOLA_TERA;
+
+mod common;
+
+use common::{test_site_path, Renderer};
+
+fn render_with_responsive_images(content: &str, responsive_images: ResponsiveImages) -> String {
+    let mut config = Config::default_for_test();
+    config.markdown.responsive_images = Some(responsive_images);
+    Renderer::new()
+        .config(config)
+        .tera(ZOLA_TERA.clone())
+        .current_page_path("posts/with-assets/index.md")
+        .base_path(test_site_path())
+        .image_processor()
+        .render(content)
+        .unwrap()
+        .body
+}
+
+#[test]
+fn can_render_colocated_image_as_responsive() {
+    let body = render_with_responsive_images(
+        r#"![The Zola logo](zola.png "Zola")"#,
+        ResponsiveImages {
+            widths: vec![8, 16],
+            formats: vec!["webp".to_owned(), "auto".to_owned()],
+            ..ResponsiveImages::default()
+        },
+    );
+    assert!(body.starts_with("<p><picture>"));
+    assert!(body
+        .contains(r#"<source type="image/webp" srcset="http://a-website.com/processed_images/"#));
+    assert!(
+        body.contains(r#"<source type="image/png" srcset="http://a-website.com/processed_images/"#)
+    );
+    assert!(body.contains(" 8w, http://a-website.com/processed_images/"));
+    assert!(body.contains(r#"sizes="100vw">"#));
+    assert!(body.contains(
+        r#"<img src="zola.png" alt="The Zola logo" title="Zola" width="256" height="256">"#
+    ));
+}
+
+#[test]
+fn leaves_remote_and_missing_images_alone() {
+    let body = render_with_responsive_images(
+        "![remote](https://example.com/image.jpg) ![missing](not-here.jpg)",
+        ResponsiveImages { widths: vec![480], ..ResponsiveImages::default() },
+    );
+    assert_eq!(
+        body,
+        "<p><img src=\"https://example.com/image.jpg\" alt=\"remote\" /> <img src=\"not-here.jpg\" alt=\"missing\" /></p>\n"
+    );
+}
+
+#[test]
+fn doesnt_render_responsive_images_without_widths() {
+    let body = render_with_responsive_images("![logo](zola.png)", ResponsiveImages::default());
+    assert_eq!(body, "<p><img src=\"zola.png\" alt=\"logo\" /></p>\n");
+}
@@ -499,3 +499,42 @@ fn can_handle_issue_1500() {
         [GOOGLE_SHORTCODE]
     );
 }
+
+const POSITIONAL_SHORTCODE: ShortCode = ShortCode::new(
+    "badge",
+    r#"<span class="badge-{{ kind | default(value="info") }}">{{ args[0] }} {{ args[1] }}</span>"#,
+    false,
+);
+
+#[test]
+fn can_use_positional_arguments() {
+    test_scenario!(
+        r#"{{ badge("new", 2) }} {{ badge('old', true, kind="warning") }}"#,
+        "<p><span class=\"badge-info\">new 2</span> <span class=\"badge-warning\">old true</span></p>\n",
+        [POSITIONAL_SHORTCODE]
+    );
+}
+
+const TABS_SHORTCODE: ShortCode =
+    ShortCode::new("tabs", r#"<div class="tabs">{{ body | safe }}</div>"#, false);
+const TAB_SHORTCODE: ShortCode = ShortCode::new(
+    "tab",
+    r#"<section title="{{ name }}" data-nth="{{ nth }}">{{ body | markdown | safe }}</section>"#,
+    false,
+);
+
+#[test]
+fn can_nest_shortcodes_with_body() {
+    test_scenario!(
+        r#"{% tabs() %}
+{% tab(name="One") %}
+*first* {{ badge("a", 1) }}
+{% end %}
+{% tab(name="Two") %}
+second
+{% end %}
+{% end %}"#,
+        "<div class=\"tabs\"><section title=\"One\" data-nth=\"1\"><p><em>first</em> <span class=\"badge-info\">a 1</span></p>\n</section>\n<section title=\"Two\" data-nth=\"2\"><p>second</p>\n</section></div>",
+        [TABS_SHORTCODE, TAB_SHORTCODE, POSITIONAL_SHORTCODE]
+    );
+}
@@ -85,6 +85,80 @@ fn multiple_on_layer() {
     );
 }
 
+#[test]
+fn can_filter_toc_levels() {
+    let config = config::Config::default_for_test();
+    let tera = tera::Tera::default();
+    let permalinks = std::collections::HashMap::new();
+    let mut context = rendering::RenderContext::new(
+        &tera,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks,
+        front_matter::InsertAnchor::None,
+    );
+    context.set_toc_levels(Some(2), Some(3));
+
+    let rendered = rendering::render_content("# A\n## B\n### C\n#### D\n## E\n", &context).unwrap();
+    assert!(vec![hh!("B", [hh!("C", [])]), hh!("E", [])] == rendered.toc);
+    // Headings outside of the ToC still get their id
+    assert!(rendered.body.contains("<h4 id=\"d\">D</h4>"));
+}
+
+#[test]
+fn can_insert_inline_toc() {
+    let mut config = config::Config::default_for_test();
+    config.markdown.toc_max_level = 2;
+    let permalinks = std::collections::HashMap::new();
+    let context = rendering::RenderContext::new(
+        &templates::ZOLA_TERA,
+        &config,
+        &config.default_language,
+        "https://example.com/page/",
+        &permalinks,
+        front_matter::InsertAnchor::None,
+    );
+
+    let rendered =
+        rendering::render_content("Intro\n\n<!-- toc -->\n\n# A\n## B\n### C\n", &context).unwrap();
+    assert_eq!(
+        rendered.body,
+        r#"<p>Intro</p>
+<nav class="toc">
+<ul>
+<li><a href="https://example.com/page/#a">A</a>
+<ul>
+<li><a href="https://example.com/page/#b">B</a></li>
+</ul></li>
+</ul>
+</nav>
+<h1 id="a">A</h1>
+<h2 id="b">B</h2>
+<h3 id="c">C</h3>
+"#
+    );
+}
+
+#[test]
+fn can_override_toc_template() {
+    let config = config::Config::default_for_test();
+    let mut tera = tera::Tera::default();
+    tera.add_raw_template("toc.html", "{% for h in toc %}[{{ h.id }}]{% endfor %}").unwrap();
+    let permalinks = std::collections::HashMap::new();
+    let context = rendering::RenderContext::new(
+        &tera,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks,
+        front_matter::InsertAnchor::None,
+    );
+
+    let rendered = rendering::render_content("# A\n<!-- toc -->\n# B\n", &context).unwrap();
+    assert_eq!(rendered.body, "<h1 id=\"a\">A</h1>\n[a][b]<h1 id=\"b\">B</h1>\n");
+}
+
 // const MD_SIMPLE1: ShortCode = ShortCode::new("simple", "Hello World!", true);
 // const MD_SIMPLE2: ShortCode = ShortCode::new("simple2", "Wow, much cool!", true);
 //
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use config::Config;
+use rendering::Rendered;
+use templates::ZOLA_TERA;
+
+mod common;
+
+use common::Renderer;
+
+fn render_wiki_links(content: &str) -> errors::Result<Rendered> {
+    let mut permalinks = HashMap::new();
+    permalinks.insert("intro.md".to_string(), "https://vincent.is/intro/".to_string());
+    permalinks.insert("a.md".to_string(), "https://vincent.is/a/".to_string());
+    permalinks.insert("b.md".to_string(), "https://vincent.is/b/".to_string());
+    let mut wiki_links = HashMap::new();
+    let intro = vec![("en".to_string(), "intro.md".to_string())];
+    wiki_links.insert("intro".to_string(), intro.clone());
+    wiki_links.insert("getting started".to_string(), intro);
+    wiki_links.insert(
+        "notes".to_string(),
+        vec![("en".to_string(), "a.md".to_string()), ("en".to_string(), "b.md".to_string())],
+    );
+
+    let mut config = Config::default_for_test();
+    config.markdown.wiki_links = true;
+    Renderer::new()
+        .config(config)
+        .tera(ZOLA_TERA.clone())
+        .permalinks(permalinks)
+        .wiki_links(wiki_links)
+        .render(content)
+}
+
+#[test]
+fn can_render_wiki_links() {
+    let res = render_wiki_links("See [[Getting Started]], [[intro#setup|the setup]] and [[a.md]].")
+        .unwrap();
+    assert_eq!(
+        res.body,
+        "<p>See <a href=\"https://vincent.is/intro/\">Getting Started</a>, \
+<a href=\"https://vincent.is/intro/#setup\">the setup</a> and \
+<a href=\"https://vincent.is/a/\">a.md</a>.</p>\n"
+    );
+    assert_eq!(
+        res.internal_links,
+        vec![
+            ("intro.md".to_string(), None),
+            ("intro.md".to_string(), Some("setup".to_string())),
+            ("a.md".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn leaves_wiki_links_in_code_alone() {
+    let res = render_wiki_links("`[[missing]]`\n\n```\n[[missing]]\n```\n\n[x] [a] [[]]").unwrap();
+    assert_eq!(
+        res.body,
+        "<p><code>[[missing]]</code></p>\n<pre><code>[[missing]]\n</code></pre>\n<p>[x] [a] [[]]</p>\n"
+    );
+}
+
+#[test]
+fn errors_on_unresolved_wiki_links() {
+    let res = render_wiki_links("Hello [[missing]]");
+    assert!(format!("{}", res.unwrap_err()).contains("[[missing]]"));
+}
+
+#[test]
+fn errors_on_ambiguous_wiki_links() {
+    let res = render_wiki_links("# [[Notes]]");
+    assert!(format!("{}", res.unwrap_err()).contains("ambiguous"));
+}
+
+#[test]
+fn doesnt_render_wiki_links_when_disabled() {
+    let body = Renderer::new().tera(ZOLA_TERA.clone()).render("[[intro]]").unwrap().body;
+    assert_eq!(body, "<p>[[intro]]</p>\n");
+}
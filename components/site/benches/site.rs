@@ -4,6 +4,7 @@ extern crate test;
 use std::env;
 
 use library::Paginator;
+use site::build_manifest::OutputSource;
 use site::Site;
 use tempfile::tempdir;
 
@@ -71,7 +72,7 @@ fn bench_render_paginated(b: &mut test::Bencher) {
     let section = library.sections_values()[0];
     let paginator = Paginator::from_section(section, &library);
 
-    b.iter(|| site.render_paginated(Vec::new(), &paginator));
+    b.iter(|| site.render_paginated(Vec::new(), &paginator, OutputSource::Section));
 }
 
 #[bench]
@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+use crate::Site;
+use config::Config;
+use errors::{Error, Result};
+use utils::fs::{create_file, unlink_file};
+use utils::minify;
+
+/// A minified asset and the source map to write next to it, if any
+pub type MinifiedAsset = (String, Option<String>);
+
+/// Minifies the CSS or JavaScript file at the given path, relative to the output directory,
+/// if the config asks for it. The minified file points to its source map when there is one.
+pub fn minify_asset(config: &Config, path: &str, content: &str) -> Result<Option<MinifiedAsset>> {
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    let minified = if path.ends_with(".css") && config.minify_css {
+        match minify::css(content, filename, config.minify_source_maps)? {
+            (css, Some(map)) => {
+                (format!("{}\n/*# sourceMappingURL={}.map */", css, filename), Some(map))
+            }
+            (css, None) => (css, None),
+        }
+    } else if (path.ends_with(".js") || path.ends_with(".mjs")) && config.minify_js {
+        match minify::js(content, filename, path.ends_with(".mjs"), config.minify_source_maps)? {
+            (js, Some(map)) => {
+                (format!("{}\n//# sourceMappingURL={}.map", js, filename), Some(map))
+            }
+            (js, None) => (js, None),
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(minified))
+}
+
+/// Minifies the exact content of a static file, which is needed both to fingerprint it
+/// and to write it
+pub fn minify_file(config: &Config, path: &str, content: &[u8]) -> Result<Option<MinifiedAsset>> {
+    let content = std::str::from_utf8(content)
+        .map_err(|e| Error::chain(format!("Failed to read {} to minify it", path), e))?;
+    minify_asset(config, path, content)
+        .map_err(|e| Error::chain(format!("Failed to minify {}", path), e))
+}
+
+/// Writes a minified asset and its source map, if any, next to it
+pub fn write_minified_asset(path: &Path, (content, map): &MinifiedAsset) -> Result<()> {
+    unlink_file(path)?;
+    create_file(path, content)?;
+
+    if let Some(map) = map {
+        let mut map_path = path.as_os_str().to_owned();
+        map_path.push(".map");
+        create_file(Path::new(&map_path), map)?;
+    }
+
+    Ok(())
+}
+
+/// Minifies the CSS and JavaScript files copied from the `static` directories that
+/// match `minify_static`. The files already minified to fingerprint them are not minified again.
+pub fn minify_static_files(site: &Site) -> Result<()> {
+    let globset = site.config.minify_static_globset()?;
+
+    let mut static_dirs = Vec::new();
+    if let Some(ref theme) = site.config.theme {
+        static_dirs.push(site.base_path.join("themes").join(theme).join("static"));
+    }
+    static_dirs.push(site.static_path.clone());
+
+    // A file can be in both the theme and the site `static` directories
+    let mut paths = BTreeSet::new();
+    for dir in static_dirs {
+        for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path =
+                entry.path().strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/");
+            if !path.starts_with("processed_images/") && globset.is_match(&path) {
+                paths.insert(path);
+            }
+        }
+    }
+
+    for path in paths {
+        let output_path = site.output_path.join(&path);
+        if let Some(minified) = site.minified_static.get(&path) {
+            write_minified_asset(&output_path, minified)?;
+            continue;
+        }
+        let content = fs::read(&output_path)
+            .map_err(|e| Error::chain(format!("Failed to read {} to minify it", path), e))?;
+        if let Some(minified) = minify_file(&site.config, &path, &content)? {
+            write_minified_asset(&output_path, &minified)?;
+        }
+    }
+
+    Ok(())
+}
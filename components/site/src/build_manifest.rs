@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde_derive::Serialize;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::compress::Encoding;
+use errors::{Error, Result};
+use utils::fs::create_file;
+
+pub const BUILD_MANIFEST_FILENAME: &str = "build-manifest.json";
+
+/// What produced a file of the output directory
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum OutputSource {
+    Page,
+    Section,
+    Taxonomy,
+    /// A page redirecting to `target`, from the `aliases` of a page or section
+    Alias {
+        target: String,
+    },
+    /// A page redirecting to `target`, from a `redirect_to` or the first page of a paginator
+    Redirect {
+        target: String,
+    },
+    Feed,
+    Sitemap,
+    Search,
+    Static,
+    Image,
+    Sass,
+    /// Anything else, eg the 404 page or `robots.txt`
+    Other,
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry<'a> {
+    #[serde(flatten)]
+    source: &'a OutputSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+}
+
+/// Writes the `build-manifest.json` listing every file of the output directory with what produced
+/// it, the SHA-256 of its content, its size and its content type, as well as the aliases and
+/// redirects that were not written as HTML pages, with only their source and target.
+/// `sources` are the sources of the files by path relative to the output directory, before they
+/// got fingerprinted, and `fingerprints` the fingerprinted paths by original path.
+pub fn write_build_manifest(
+    output_path: &Path,
+    sources: &HashMap<String, OutputSource>,
+    fingerprints: &HashMap<String, String>,
+) -> Result<()> {
+    let originals: HashMap<_, _> =
+        fingerprints.iter().map(|(k, v)| (v.as_str(), k.as_str())).collect();
+    let mut manifest = BTreeMap::new();
+
+    for entry in WalkDir::new(output_path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path =
+            entry.path().strip_prefix(output_path).unwrap().to_string_lossy().replace('\\', "/");
+        if path == BUILD_MANIFEST_FILENAME {
+            continue;
+        }
+
+        // Compressed copies and source maps come from the same place as the file they are for
+        let mut encoding = None;
+        let mut base_path = path.as_str();
+        for enc in &[Encoding::Gzip, Encoding::Brotli] {
+            let suffix = format!(".{}", enc.extension());
+            if let Some(p) =
+                base_path.strip_suffix(&suffix).filter(|p| output_path.join(p).is_file())
+            {
+                encoding = Some(enc.name());
+                base_path = p;
+            }
+        }
+        let uncompressed_path = base_path;
+        // The source maps keep the original name of fingerprinted files
+        if let Some(p) = base_path
+            .strip_suffix(".map")
+            .filter(|p| output_path.join(p).is_file() || fingerprints.contains_key(*p))
+        {
+            base_path = p;
+        }
+        let original_path = originals.get(base_path).copied().unwrap_or(base_path);
+        let source = sources.get(original_path).unwrap_or(&OutputSource::Other);
+
+        let content = fs::read(entry.path())
+            .map_err(|e| Error::chain(format!("Failed to read {} to hash it", path), e))?;
+        let content_type = if uncompressed_path.ends_with(".map") {
+            "application/json".to_string()
+        } else {
+            mime_guess::from_path(uncompressed_path)
+                .first_or_octet_stream()
+                .essence_str()
+                .to_string()
+        };
+        manifest.insert(
+            path,
+            ManifestEntry {
+                source,
+                hash: Some(format!("{:x}", Sha256::digest(&content))),
+                size: Some(content.len() as u64),
+                content_type: Some(content_type),
+                encoding,
+            },
+        );
+    }
+
+    // Aliases and redirects done by the web server instead of HTML pages have no file
+    for (path, source) in sources {
+        if matches!(source, OutputSource::Alias { .. } | OutputSource::Redirect { .. }) {
+            manifest.entry(path.clone()).or_insert(ManifestEntry {
+                source,
+                hash: None,
+                size: None,
+                content_type: None,
+                encoding: None,
+            });
+        }
+    }
+
+    create_file(
+        &output_path.join(BUILD_MANIFEST_FILENAME),
+        &serde_json::to_string_pretty(&manifest).unwrap(),
+    )
+}
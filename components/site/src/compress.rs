@@ -0,0 +1,124 @@
+use std::fs;
+use std::io::Write;
+
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use crate::Site;
+use errors::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    /// The name of the encoding in the `Accept-Encoding` and `Content-Encoding` headers
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Brotli => "br",
+        }
+    }
+
+    /// The extension added to the compressed copies of the files
+    pub fn extension(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gz",
+            Encoding::Brotli => "br",
+        }
+    }
+}
+
+/// Compresses the content with the given encoding. `best` trades speed for the smallest output,
+/// which is what we want for files compressed once when building the site
+pub fn compress(content: &[u8], encoding: Encoding, best: bool) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let level =
+                if best { flate2::Compression::best() } else { flate2::Compression::fast() };
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+            encoder.write_all(content)?;
+            Ok(encoder.finish()?)
+        }
+        Encoding::Brotli => {
+            let quality = if best { 11 } else { 4 };
+            let mut compressed = Vec::new();
+            {
+                let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, quality, 22);
+                writer.write_all(content)?;
+            }
+            Ok(compressed)
+        }
+    }
+}
+
+/// Writes the gzip and/or brotli copies of the files of the output directory selected by
+/// `compress_output`, eg `index.html.gz` and `index.html.br` next to `index.html`
+pub fn compress_output(site: &Site) -> Result<()> {
+    let compression = &site.config.compress_output;
+    let mut encodings = Vec::new();
+    if compression.gzip {
+        encodings.push(Encoding::Gzip);
+    }
+    if compression.brotli {
+        encodings.push(Encoding::Brotli);
+    }
+
+    let files: Vec<_> = WalkDir::new(&site.output_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            let extension = e.path().extension().and_then(|e| e.to_str()).unwrap_or_default();
+            let size = e.metadata().map(|m| m.len() as usize).unwrap_or_default();
+            compression.should_compress(extension, size)
+        })
+        .map(|e| e.into_path())
+        .collect();
+
+    files
+        .par_iter()
+        .map(|path| {
+            let content = fs::read(path).map_err(|e| {
+                Error::chain(format!("Failed to read {} to compress it", path.display()), e)
+            })?;
+            for encoding in &encodings {
+                let mut compressed_path = path.as_os_str().to_owned();
+                compressed_path.push(".");
+                compressed_path.push(encoding.extension());
+                fs::write(&compressed_path, compress(&content, *encoding, true)?).map_err(|e| {
+                    Error::chain(format!("Failed to write {:?}", compressed_path), e)
+                })?;
+            }
+            Ok(())
+        })
+        .collect::<Result<()>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::{compress, Encoding};
+
+    #[test]
+    fn can_compress_content() {
+        let content = "<p>Hello world</p>".repeat(100);
+
+        for best in &[true, false] {
+            let gzipped = compress(content.as_bytes(), Encoding::Gzip, *best).unwrap();
+            assert!(gzipped.len() < content.len());
+            let mut decoded = String::new();
+            flate2::read::GzDecoder::new(&gzipped[..]).read_to_string(&mut decoded).unwrap();
+            assert_eq!(decoded, content);
+
+            let brotlied = compress(content.as_bytes(), Encoding::Brotli, *best).unwrap();
+            assert!(brotlied.len() < content.len());
+            let mut decoded = String::new();
+            brotli::Decompressor::new(&brotlied[..], 4096).read_to_string(&mut decoded).unwrap();
+            assert_eq!(decoded, content);
+        }
+    }
+}
@@ -60,6 +60,7 @@ pub fn render_feed(
 
     context.insert("pages", &p);
     context.insert("config", &site.config.serialize(lang));
+    context.insert("data", &site.data);
     context.insert("lang", lang);
 
     let feed_filename = &site.config.feed_filename;
@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::assets::{minify_file, MinifiedAsset};
+use crate::Site;
+use errors::{Error, Result};
+use utils::fs::create_file;
+
+/// Returns the path with the hash of the content before its extension,
+/// eg `css/site.css` -> `css/site.0123456789abcdef.css`
+pub fn fingerprinted_path(path: &str, content: &[u8]) -> String {
+    let hash = format!("{:x}", Sha256::digest(content));
+    let (dir, filename) = match path.rfind('/') {
+        Some(i) => path.split_at(i + 1),
+        None => ("", path),
+    };
+    match filename.rfind('.') {
+        Some(i) if i > 0 => {
+            format!("{}{}.{}{}", dir, &filename[..i], &hash[..16], &filename[i..])
+        }
+        _ => format!("{}{}.{}", dir, filename, &hash[..16]),
+    }
+}
+
+/// Finds the static files and the Sass output matching `fingerprint.files` and returns
+/// their fingerprinted paths, keyed by their original paths relative to the output directory.
+/// The Sass output is the one compiled in `Site::load` and the static files to minify are
+/// minified to know the hash of their output, which is returned to be written as is.
+pub fn build_manifest(
+    site: &Site,
+) -> Result<(HashMap<String, String>, HashMap<String, MinifiedAsset>)> {
+    let globset = site.config.fingerprint.globset()?;
+    let minify_globset = site.config.minify_static_globset()?;
+    let mut manifest = HashMap::new();
+    let mut minified_static = HashMap::new();
+
+    // Same order as when copying them: the user files overwrite the theme files
+    let mut static_dirs = Vec::new();
+    if let Some(ref theme) = site.config.theme {
+        static_dirs.push(site.base_path.join("themes").join(theme).join("static"));
+    }
+    static_dirs.push(site.static_path.clone());
+
+    for dir in static_dirs {
+        for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path =
+                entry.path().strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/");
+            // Processed images already have a hash in their names
+            if path.starts_with("processed_images/") || !globset.is_match(&path) {
+                continue;
+            }
+            let content = fs::read(entry.path()).map_err(|e| {
+                Error::chain(format!("Failed to read {} to fingerprint it", path), e)
+            })?;
+            let minified = if minify_globset.is_match(&path) {
+                minify_file(&site.config, &path, &content)?
+            } else {
+                None
+            };
+            // A file of the site overwrites the one of the theme, minified or not
+            let fingerprinted = match minified {
+                Some(minified) => {
+                    let fingerprinted = fingerprinted_path(&path, minified.0.as_bytes());
+                    minified_static.insert(path.clone(), minified);
+                    fingerprinted
+                }
+                None => {
+                    minified_static.remove(&path);
+                    fingerprinted_path(&path, &content)
+                }
+            };
+            manifest.insert(path, fingerprinted);
+        }
+    }
+
+    for compiled in site.compiled_sass.values() {
+        for (path, (css, _)) in compiled {
+            if globset.is_match(path) {
+                manifest.insert(path.clone(), fingerprinted_path(path, css.as_bytes()));
+            }
+        }
+    }
+
+    Ok((manifest, minified_static))
+}
+
+/// Renames the fingerprinted files of the output directory and writes the
+/// `asset-manifest.json` listing them
+pub fn rename_assets(manifest: &HashMap<String, String>, output_path: &Path) -> Result<()> {
+    for (path, fingerprinted) in manifest {
+        let original = output_path.join(path);
+        if original.exists() {
+            fs::rename(&original, output_path.join(fingerprinted)).map_err(|e| {
+                Error::chain(format!("Failed to rename {} to {}", path, fingerprinted), e)
+            })?;
+        }
+    }
+
+    let sorted: BTreeMap<_, _> = manifest.iter().collect();
+    create_file(
+        &output_path.join("asset-manifest.json"),
+        &serde_json::to_string_pretty(&sorted).unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fingerprinted_path;
+
+    #[test]
+    fn can_fingerprint_paths() {
+        let hash = "9f86d081884c7d65";
+        assert_eq!(fingerprinted_path("site.css", b"test"), format!("site.{}.css", hash));
+        assert_eq!(fingerprinted_path("js/app.min.js", b"test"), format!("js/app.min.{}.js", hash));
+        assert_eq!(fingerprinted_path("fonts/LICENSE", b"test"), format!("fonts/LICENSE.{}", hash));
+        assert_eq!(fingerprinted_path(".well-known/x", b"test"), format!(".well-known/x.{}", hash));
+        assert_eq!(
+            fingerprinted_path("files/.htaccess", b"test"),
+            format!("files/.htaccess.{}", hash)
+        );
+    }
+}
@@ -1,31 +1,42 @@
+pub mod assets;
+pub mod build_manifest;
+pub mod compress;
 pub mod feed;
+pub mod fingerprint;
 pub mod link_checking;
+pub mod redirects;
 pub mod sass;
 pub mod sitemap;
 pub mod tpls;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::remove_dir_all;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
 
 use lazy_static::lazy_static;
 use rayon::prelude::*;
-use tera::{Context, Tera};
+use tera::{to_value, Context, Tera, Value};
 use walkdir::{DirEntry, WalkDir};
 
-use config::{get_config, Config};
+use crate::assets::MinifiedAsset;
+use crate::build_manifest::{write_build_manifest, OutputSource};
+use crate::sass::CompiledSass;
+use config::{get_config, Config, HighlightTheme};
 use errors::{bail, Error, Result};
 use front_matter::InsertAnchor;
 use library::{find_taxonomies, Library, Page, Paginator, Section, Taxonomy};
 use relative_path::RelativePathBuf;
 use std::time::Instant;
+use templates::global_fns::load_data_directory;
 use templates::{load_tera, render_redirect_template};
 use utils::fs::{
     copy_directory, copy_file_if_needed, create_directory, create_file, ensure_directory_exists,
+    read_file, sync_directory, SyncStats,
 };
 use utils::minify;
 use utils::net::get_available_port;
+use utils::site::WikiLinkTargets;
 use utils::templates::{render_template, ShortcodeDefinition};
 
 lazy_static! {
@@ -49,6 +60,8 @@ pub struct Site {
     /// The parsed config for the site
     pub config: Config,
     pub tera: Tera,
+    /// The content of the files of the `data` directory, serialized once for all the templates
+    data: Value,
     imageproc: Arc<Mutex<imageproc::Processor>>,
     // the live reload port to be used if there is one
     pub live_reload: Option<u16>,
@@ -65,6 +78,16 @@ pub struct Site {
     include_drafts: bool,
     build_mode: BuildMode,
     shortcode_definitions: HashMap<String, ShortcodeDefinition>,
+    /// The Sass compiled to fingerprint it, by directory, written as is by `build`
+    compiled_sass: HashMap<PathBuf, CompiledSass>,
+    /// The static files minified to fingerprint them, by path relative to the output directory,
+    /// written as is by `build`
+    minified_static: HashMap<String, MinifiedAsset>,
+    /// What produced the files of the output directory, for the build manifest
+    output_sources: Mutex<HashMap<String, OutputSource>>,
+    /// The output directory `build_and_sync` syncs to, `output_path` being the temporary
+    /// directory the site is built in
+    sync_path: Option<PathBuf>,
 }
 
 impl Site {
@@ -79,6 +102,11 @@ impl Site {
             // Grab data from the extra section of the theme
             config.merge_with_theme(path.join("themes").join(&theme).join("theme.toml"), &theme)?;
         }
+        rendering::check_language_highlighters(&config)?;
+
+        // The files of the `data` directory are available in every template as `data`
+        let data = load_data_directory(&path.join("data"))
+            .map_err(|e| Error::chain("Failed to load the `data` directory", e))?;
 
         let tera = load_tera(path, &config)?;
         let shortcode_definitions = utils::templates::get_shortcodes(&tera);
@@ -92,6 +120,7 @@ impl Site {
             base_path: path.to_path_buf(),
             config,
             tera,
+            data,
             imageproc: Arc::new(Mutex::new(imageproc)),
             live_reload: None,
             output_path,
@@ -104,6 +133,10 @@ impl Site {
             library: Arc::new(RwLock::new(Library::new(0, 0, false))),
             build_mode: BuildMode::Disk,
             shortcode_definitions,
+            compiled_sass: HashMap::new(),
+            minified_static: HashMap::new(),
+            output_sources: Mutex::new(HashMap::new()),
+            sync_path: None,
         };
 
         Ok(site)
@@ -122,6 +155,21 @@ impl Site {
         self.include_drafts = true;
     }
 
+    /// Set the site to never request the URLs given to `load_data`, only using the responses
+    /// in the cache and in the fixtures. Needs to be called before loading it
+    pub fn enable_offline_mode(&mut self) {
+        self.config.load_data.offline = true;
+    }
+
+    /// Set the site to be built in a temporary directory next to the output directory by
+    /// `build_and_sync`. Needs to be called after setting the output path and before loading
+    /// the site since the template functions read the files of the directory it is built in
+    pub fn enable_sync_mode(&mut self) {
+        let dir_name = self.output_path.file_name().unwrap_or_default().to_string_lossy();
+        let build_path = self.output_path.with_file_name(format!(".{}-sync", dir_name));
+        self.sync_path = Some(std::mem::replace(&mut self.output_path, build_path));
+    }
+
     /// The index sections are ALWAYS at those paths
     /// There are one index section for the default language + 1 per language
     fn index_section_paths(&self) -> Vec<(PathBuf, Option<&str>)> {
@@ -282,6 +330,23 @@ impl Site {
         // taxonomy Tera fns are loaded in `register_early_global_fns`
         // so we do need to populate it first.
         self.populate_taxonomies()?;
+        // The fingerprinted names of the assets need to be known before rendering anything,
+        // `zola serve` keeps the original names so the CSS can be reloaded on changes and
+        // `zola check` doesn't write any
+        self.compiled_sass.clear();
+        self.minified_static.clear();
+        if self.config.fingerprint.enabled
+            && !self.config.is_in_serve_mode()
+            && !self.config.is_in_check_mode()
+        {
+            for dir in sass::sass_dirs(&self.base_path, &self.config) {
+                let compiled = sass::compile_sass_files(&dir, &self.config)?;
+                self.compiled_sass.insert(dir, compiled);
+            }
+            let (manifest, minified_static) = fingerprint::build_manifest(self)?;
+            self.config.fingerprint.manifest = manifest;
+            self.minified_static = minified_static;
+        }
         tpls::register_early_global_fns(self)?;
         self.populate_sections();
         self.render_markdown()?;
@@ -347,6 +412,9 @@ impl Site {
         let permalinks = &self.permalinks;
         let tera = &self.tera;
         let config = &self.config;
+        let data = &self.data;
+        let base_path = &self.base_path;
+        let wiki_links = &self.wiki_link_targets();
 
         // This is needed in the first place because of silly borrow checker
         let mut pages_insert_anchors = HashMap::new();
@@ -358,36 +426,69 @@ impl Site {
         }
 
         let mut library = self.library.write().expect("Get lock for render_markdown");
+        // The sections are rendered first so their content is available to the shortcodes
+        // of their pages
         library
-            .pages_mut()
+            .sections_mut()
             .values_mut()
             .collect::<Vec<_>>()
             .par_iter_mut()
-            .map(|page| {
-                let insert_anchor = pages_insert_anchors[&page.file.path];
-                page.render_markdown(
+            .map(|section| {
+                section.render_markdown(
                     permalinks,
+                    wiki_links,
                     tera,
                     config,
-                    insert_anchor,
+                    data,
+                    base_path,
                     &self.shortcode_definitions,
+                    Some(&self.imageproc),
                 )
             })
             .collect::<Result<()>>()?;
 
+        let mut serialized_sections = HashMap::new();
+        for section in library.sections().values() {
+            let serialized = to_value(section.to_serialized_basic(&library)).unwrap();
+            serialized_sections.insert(section.file.path.clone(), serialized);
+        }
+
         library
-            .sections_mut()
+            .pages_mut()
             .values_mut()
             .collect::<Vec<_>>()
             .par_iter_mut()
-            .map(|section| {
-                section.render_markdown(permalinks, tera, config, &self.shortcode_definitions)
+            .map(|page| {
+                let insert_anchor = pages_insert_anchors[&page.file.path];
+                let parent_section = serialized_sections
+                    .get(&self.find_parent_section_path(&page.file.parent, &page.lang));
+                page.render_markdown(
+                    permalinks,
+                    wiki_links,
+                    tera,
+                    config,
+                    data,
+                    base_path,
+                    insert_anchor,
+                    parent_section,
+                    &self.shortcode_definitions,
+                    Some(&self.imageproc),
+                )
             })
             .collect::<Result<()>>()?;
 
         Ok(())
     }
 
+    /// The pages the `[[wiki links]]` can point to, only indexed if they are enabled
+    pub fn wiki_link_targets(&self) -> WikiLinkTargets {
+        if self.config.markdown.wiki_links {
+            self.library.read().unwrap().wiki_link_targets()
+        } else {
+            HashMap::new()
+        }
+    }
+
     /// Add a page to the site
     /// The `render` parameter is used in the serve command with --fast, when rebuilding a page.
     pub fn add_page(&mut self, mut page: Page, render_md: bool) -> Result<()> {
@@ -395,12 +496,24 @@ impl Site {
         if render_md {
             let insert_anchor =
                 self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
+            let parent_section = {
+                let library = self.library.read().unwrap();
+                let parent_path = self.find_parent_section_path(&page.file.parent, &page.lang);
+                library
+                    .get_section(&parent_path)
+                    .map(|s| to_value(s.to_serialized_basic(&library)).unwrap())
+            };
             page.render_markdown(
                 &self.permalinks,
+                &self.wiki_link_targets(),
                 &self.tera,
                 &self.config,
+                &self.data,
+                &self.base_path,
                 insert_anchor,
+                parent_section.as_ref(),
                 &self.shortcode_definitions,
+                Some(&self.imageproc),
             )?;
         }
 
@@ -430,9 +543,13 @@ impl Site {
         if render_md {
             section.render_markdown(
                 &self.permalinks,
+                &self.wiki_link_targets(),
                 &self.tera,
                 &self.config,
+                &self.data,
+                &self.base_path,
                 &self.shortcode_definitions,
+                Some(&self.imageproc),
             )?;
         }
         let mut library = self.library.write().expect("Get lock for add_section");
@@ -460,17 +577,23 @@ impl Site {
         parent_path: &Path,
         lang: &str,
     ) -> InsertAnchor {
-        let parent = if lang != self.config.default_language {
-            parent_path.join(format!("_index.{}.md", lang))
-        } else {
-            parent_path.join("_index.md")
-        };
+        let parent = self.find_parent_section_path(parent_path, lang);
         match self.library.read().unwrap().get_section(&parent) {
             Some(s) => s.meta.insert_anchor_links,
             None => InsertAnchor::None,
         }
     }
 
+    /// Finds the path of the `_index.md` file of the section in the directory at `parent_path`,
+    /// in the given language
+    fn find_parent_section_path(&self, parent_path: &Path, lang: &str) -> PathBuf {
+        if lang != self.config.default_language {
+            parent_path.join(format!("_index.{}.md", lang))
+        } else {
+            parent_path.join("_index.md")
+        }
+    }
+
     /// Find out the direct subsections of each subsection if there are some
     /// as well as the pages for each section
     pub fn populate_sections(&mut self) {
@@ -522,11 +645,47 @@ impl Site {
         Ok(())
     }
 
+    /// Records the files copied from the `static` folders for the build manifest
+    fn record_static_files(&self) {
+        if !self.config.generate_build_manifest {
+            return;
+        }
+        let mut static_dirs = vec![self.static_path.clone()];
+        if let Some(ref theme) = self.config.theme {
+            static_dirs.push(self.base_path.join("themes").join(theme).join("static"));
+        }
+
+        for dir in static_dirs {
+            for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let path =
+                    entry.path().strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/");
+                let source = if path.starts_with("processed_images/") {
+                    OutputSource::Image
+                } else {
+                    OutputSource::Static
+                };
+                self.record_output(path, source);
+            }
+        }
+    }
+
     pub fn num_img_ops(&self) -> usize {
         let imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (num_img_ops)");
         imageproc.num_img_ops()
     }
 
+    /// The files included in code blocks of all pages and sections, used by `zola serve`
+    /// to know which files outside of the usual folders it needs to watch
+    pub fn included_files(&self) -> HashSet<PathBuf> {
+        let library = self.library.read().expect("Get lock for included_files");
+        let pages = library.pages_values().into_iter().flat_map(|p| p.included_files.iter());
+        let sections = library.sections_values().into_iter().flat_map(|s| s.included_files.iter());
+        pages.chain(sections).cloned().collect()
+    }
+
     pub fn process_images(&self) -> Result<()> {
         let mut imageproc =
             self.imageproc.lock().expect("Couldn't lock imageproc (process_images)");
@@ -552,6 +711,7 @@ impl Site {
         filename: &str,
         content: String,
         create_dirs: bool,
+        source: OutputSource,
     ) -> Result<PathBuf> {
         let write_dirs = self.build_mode == BuildMode::Disk || create_dirs;
         ensure_directory_exists(&self.output_path)?;
@@ -572,6 +732,16 @@ impl Site {
             create_directory(&current_path)?;
         }
 
+        let minify_inline = self.config.minify_css || self.config.minify_js;
+        let content = if !filename.ends_with("html") || !minify_inline {
+            content
+        } else {
+            let inline = minify::inline(&content, self.config.minify_css, self.config.minify_js);
+            inline.map_err(|e| {
+                Error::chain(format!("Failed to minify inline code of {}", filename), e)
+            })?
+        };
+
         let final_content = if !filename.ends_with("html") || !self.config.minify_html {
             content
         } else {
@@ -585,6 +755,7 @@ impl Site {
             BuildMode::Disk => {
                 let end_path = current_path.join(filename);
                 create_file(&end_path, &final_content)?;
+                self.record_output(site_path.join(filename).to_string(), source);
             }
             BuildMode::Memory => {
                 let site_path =
@@ -597,17 +768,45 @@ impl Site {
         Ok(current_path)
     }
 
-    fn copy_asset(&self, src: &Path, dest: &Path) -> Result<()> {
-        copy_file_if_needed(src, dest, self.config.hard_link_static)
+    fn copy_asset(&self, src: &Path, dest: &Path, source: OutputSource) -> Result<()> {
+        copy_file_if_needed(src, dest, self.config.hard_link_static)?;
+        if let Ok(path) = dest.strip_prefix(&self.output_path) {
+            self.record_output(path.to_string_lossy().replace('\\', "/"), source);
+        }
+        Ok(())
+    }
+
+    /// Keeps track of what produced a file of the output directory if we need to
+    /// write the build manifest
+    fn record_output(&self, path: String, source: OutputSource) {
+        if self.config.generate_build_manifest && self.build_mode == BuildMode::Disk {
+            self.output_sources.lock().expect("Couldn't lock output sources").insert(path, source);
+        }
+    }
+
+    /// Records a redirect done by the web server rather than an HTML page, at the path the page
+    /// would have been written to, so it is still listed in the build manifest
+    fn record_redirect(&self, components: &[&str], filename: &str, source: OutputSource) {
+        let mut site_path = RelativePathBuf::new();
+        for component in components {
+            site_path.push(component);
+        }
+        self.record_output(site_path.join(filename).to_string(), source);
     }
 
     /// Renders a single content page
     pub fn render_page(&self, page: &Page) -> Result<()> {
-        let output = page.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
+        let output =
+            page.render_html(&self.tera, &self.config, &self.data, &self.library.read().unwrap())?;
         let content = self.inject_livereload(output);
         let components: Vec<&str> = page.path.split('/').collect();
-        let current_path =
-            self.write_content(&components, "index.html", content, !page.assets.is_empty())?;
+        let current_path = self.write_content(
+            &components,
+            "index.html",
+            content,
+            !page.assets.is_empty(),
+            OutputSource::Page,
+        )?;
 
         // Copy any asset we found previously into the same directory as the index.html
         for asset in &page.assets {
@@ -619,6 +818,7 @@ impl Site {
                         .strip_prefix(&page.file.path.parent().unwrap())
                         .expect("Couldn't get filename from page asset"),
                 ),
+                OutputSource::Page,
             )?;
         }
 
@@ -632,20 +832,19 @@ impl Site {
         if self.build_mode == BuildMode::Disk {
             self.clean()?;
         }
+        self.output_sources.lock().expect("Couldn't lock output sources").clear();
         start = log_time(start, "Cleaned folder");
 
         // Generate/move all assets before rendering any content
-        if let Some(ref theme) = self.config.theme {
-            let theme_path = self.base_path.join("themes").join(theme);
-            if theme_path.join("sass").exists() {
-                sass::compile_sass(&theme_path, &self.output_path)?;
-                start = log_time(start, "Compiled theme Sass");
+        for dir in sass::sass_dirs(&self.base_path, &self.config) {
+            let css_paths = match self.compiled_sass.get(&dir) {
+                Some(compiled) => sass::write_sass(compiled, &self.output_path)?,
+                None => sass::compile_sass(&dir, &self.output_path, &self.config)?,
+            };
+            for css_path in css_paths {
+                self.record_output(css_path, OutputSource::Sass);
             }
-        }
-
-        if self.config.compile_sass {
-            sass::compile_sass(&self.base_path, &self.output_path)?;
-            start = log_time(start, "Compiled own Sass");
+            start = log_time(start, "Compiled Sass");
         }
 
         if self.config.build_search_index {
@@ -703,11 +902,58 @@ impl Site {
         start = log_time(start, "Processed images");
         // Processed images will be in static so the last step is to copy it
         self.copy_static_directories()?;
-        log_time(start, "Copied static dir");
+        self.record_static_files();
+        start = log_time(start, "Copied static dir");
+        // After copying the static directories since the redirects are added to the
+        // `_redirects` and `.htaccess` files found there
+        if !self.config.redirects.formats.is_empty() && self.build_mode == BuildMode::Disk {
+            redirects::write_redirect_files(self)?;
+            start = log_time(start, "Wrote redirect files");
+        }
+        // Before fingerprinting them since their hashes are the ones of the minified files
+        if (self.config.minify_css || self.config.minify_js) && !self.config.is_in_serve_mode() {
+            assets::minify_static_files(self)?;
+            start = log_time(start, "Minified static files");
+        }
+        if !self.config.fingerprint.manifest.is_empty() {
+            fingerprint::rename_assets(&self.config.fingerprint.manifest, &self.output_path)?;
+            start = log_time(start, "Fingerprinted assets");
+        }
+        // `zola serve` compresses the responses itself since the pages are only in memory
+        if self.config.compress_output.enabled && !self.config.is_in_serve_mode() {
+            compress::compress_output(self)?;
+            start = log_time(start, "Compressed output");
+        }
+        if self.config.generate_build_manifest && self.build_mode == BuildMode::Disk {
+            write_build_manifest(
+                &self.output_path,
+                &self.output_sources.lock().expect("Couldn't lock output sources"),
+                &self.config.fingerprint.manifest,
+            )?;
+            log_time(start, "Wrote build manifest");
+        }
 
         Ok(())
     }
 
+    /// Builds the site in the temporary directory picked by `enable_sync_mode` and only writes
+    /// the files whose content changed to the output directory, removing the files the build
+    /// didn't produce. The files that didn't change keep their modification times.
+    pub fn build_and_sync(&mut self) -> Result<SyncStats> {
+        let output_path = match self.sync_path {
+            Some(ref p) => p.clone(),
+            None => bail!("The site needs to be in sync mode to be synced"),
+        };
+
+        let res = self.build().and_then(|_| sync_directory(&self.output_path, &output_path));
+        if self.output_path.exists() {
+            remove_dir_all(&self.output_path)
+                .map_err(|e| Error::chain("Couldn't delete temporary build directory", e))?;
+        }
+
+        res
+    }
+
     pub fn render_themes_css(&self) -> Result<()> {
         ensure_directory_exists(&self.static_path)?;
 
@@ -719,6 +965,16 @@ impl Site {
             }
         }
 
+        if let HighlightTheme::LightDark(ref themes) = self.config.markdown.highlight_theme {
+            let p = self.static_path.join(&themes.filename);
+            let content = self.config.markdown.export_light_dark_theme_css(themes);
+            // Rewritten whenever it doesn't match so changing the themes or the dark mode
+            // doesn't leave a stale stylesheet behind
+            if read_file(&p).ok().as_deref() != Some(content.as_str()) {
+                create_file(&p, &content)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -727,8 +983,9 @@ impl Site {
         // TODO: add those to the SITE_CONTENT map
 
         // index first
+        let index_filename = format!("search_index.{}.js", self.config.default_language);
         create_file(
-            &self.output_path.join(&format!("search_index.{}.js", self.config.default_language)),
+            &self.output_path.join(&index_filename),
             &format!(
                 "window.searchIndex = {};",
                 search::build_index(
@@ -738,25 +995,35 @@ impl Site {
                 )?
             ),
         )?;
+        self.record_output(index_filename, OutputSource::Search);
 
         for (code, language) in &self.config.other_languages() {
             if code != &self.config.default_language && language.build_search_index {
+                let index_filename = format!("search_index.{}.js", &code);
                 create_file(
-                    &self.output_path.join(&format!("search_index.{}.js", &code)),
+                    &self.output_path.join(&index_filename),
                     &format!(
                         "window.searchIndex = {};",
                         search::build_index(code, &self.library.read().unwrap(), &self.config)?
                     ),
                 )?;
+                self.record_output(index_filename, OutputSource::Search);
             }
         }
 
         // then elasticlunr.min.js
         create_file(&self.output_path.join("elasticlunr.min.js"), search::ELASTICLUNR_JS)?;
+        self.record_output("elasticlunr.min.js".to_string(), OutputSource::Search);
 
         Ok(())
     }
 
+    /// Whether to write the HTML pages redirecting from the `aliases` and `redirect_to`.
+    /// `zola serve` always needs them since it doesn't read the redirect files
+    fn writes_html_redirects(&self) -> bool {
+        self.config.redirects.html || self.config.is_in_serve_mode()
+    }
+
     fn render_alias(&self, alias: &str, permalink: &str) -> Result<()> {
         let mut split = alias.split('/').collect::<Vec<_>>();
 
@@ -770,8 +1037,13 @@ impl Site {
             }
             None => "index.html",
         };
+        let source = OutputSource::Alias { target: permalink.to_string() };
+        if !self.writes_html_redirects() {
+            self.record_redirect(&split, page_name, source);
+            return Ok(());
+        }
         let content = render_redirect_template(permalink, &self.tera)?;
-        self.write_content(&split, page_name, content, false)?;
+        self.write_content(&split, page_name, content, false, source)?;
         Ok(())
     }
 
@@ -798,10 +1070,11 @@ impl Site {
         ensure_directory_exists(&self.output_path)?;
         let mut context = Context::new();
         context.insert("config", &self.config.serialize(&self.config.default_language));
+        context.insert("data", &self.data);
         context.insert("lang", &self.config.default_language);
         let output = render_template("404.html", &self.tera, context, &self.config.theme)?;
         let content = self.inject_livereload(output);
-        self.write_content(&[], "404.html", content, false)?;
+        self.write_content(&[], "404.html", content, false, OutputSource::Other)?;
         Ok(())
     }
 
@@ -810,8 +1083,9 @@ impl Site {
         ensure_directory_exists(&self.output_path)?;
         let mut context = Context::new();
         context.insert("config", &self.config.serialize(&self.config.default_language));
+        context.insert("data", &self.data);
         let content = render_template("robots.txt", &self.tera, context, &self.config.theme)?;
-        self.write_content(&[], "robots.txt", content, false)?;
+        self.write_content(&[], "robots.txt", content, false, OutputSource::Other)?;
         Ok(())
     }
 
@@ -838,10 +1112,14 @@ impl Site {
 
         components.push(taxonomy.slug.as_ref());
 
-        let list_output =
-            taxonomy.render_all_terms(&self.tera, &self.config, &self.library.read().unwrap())?;
+        let list_output = taxonomy.render_all_terms(
+            &self.tera,
+            &self.config,
+            &self.data,
+            &self.library.read().unwrap(),
+        )?;
         let content = self.inject_livereload(list_output);
-        self.write_content(&components, "index.html", content, false)?;
+        self.write_content(&components, "index.html", content, false, OutputSource::Taxonomy)?;
 
         let library = self.library.read().unwrap();
         taxonomy
@@ -861,12 +1139,24 @@ impl Site {
                             &self.tera,
                             &self.config.theme,
                         ),
+                        OutputSource::Taxonomy,
                     )?;
                 } else {
-                    let single_output =
-                        taxonomy.render_term(item, &self.tera, &self.config, &library)?;
+                    let single_output = taxonomy.render_term(
+                        item,
+                        &self.tera,
+                        &self.config,
+                        &self.data,
+                        &library,
+                    )?;
                     let content = self.inject_livereload(single_output);
-                    self.write_content(&comp, "index.html", content, false)?;
+                    self.write_content(
+                        &comp,
+                        "index.html",
+                        content,
+                        false,
+                        OutputSource::Taxonomy,
+                    )?;
                 }
 
                 if taxonomy.kind.feed {
@@ -902,7 +1192,7 @@ impl Site {
             let mut context = Context::new();
             context.insert("entries", &all_sitemap_entries);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
-            self.write_content(&[], "sitemap.xml", sitemap, false)?;
+            self.write_content(&[], "sitemap.xml", sitemap, false, OutputSource::Sitemap)?;
             return Ok(());
         }
 
@@ -915,7 +1205,7 @@ impl Site {
             context.insert("entries", &chunk);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
             let file_name = format!("sitemap{}.xml", i + 1);
-            self.write_content(&[], &file_name, sitemap, false)?;
+            self.write_content(&[], &file_name, sitemap, false, OutputSource::Sitemap)?;
             let mut sitemap_url = self.config.make_permalink(&file_name);
             sitemap_url.pop(); // Remove trailing slash
             sitemap_index.push(sitemap_url);
@@ -930,7 +1220,7 @@ impl Site {
             main_context,
             &self.config.theme,
         )?;
-        self.write_content(&[], "sitemap.xml", sitemap, false)?;
+        self.write_content(&[], "sitemap.xml", sitemap, false, OutputSource::Sitemap)?;
 
         Ok(())
     }
@@ -965,9 +1255,10 @@ impl Site {
                 feed_filename,
                 feed,
                 false,
+                OutputSource::Feed,
             )?;
         } else {
-            self.write_content(&[], feed_filename, feed, false)?;
+            self.write_content(&[], feed_filename, feed, false, OutputSource::Feed)?;
         }
         Ok(())
     }
@@ -1021,6 +1312,7 @@ impl Site {
                         .strip_prefix(&section.file.path.parent().unwrap())
                         .expect("Failed to get asset filename for section"),
                 ),
+                OutputSource::Section,
             )?;
         }
 
@@ -1038,11 +1330,17 @@ impl Site {
 
         if let Some(ref redirect_to) = section.meta.redirect_to {
             let permalink = self.config.make_permalink(redirect_to);
+            if !self.writes_html_redirects() {
+                let source = OutputSource::Redirect { target: permalink };
+                self.record_redirect(&components, "index.html", source);
+                return Ok(());
+            }
             self.write_content(
                 &components,
                 "index.html",
                 render_redirect_template(&permalink, &self.tera)?,
                 create_directories,
+                OutputSource::Redirect { target: permalink.clone() },
             )?;
 
             return Ok(());
@@ -1052,12 +1350,17 @@ impl Site {
             self.render_paginated(
                 components,
                 &Paginator::from_section(section, &self.library.read().unwrap()),
+                OutputSource::Section,
             )?;
         } else {
-            let output =
-                section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
+            let output = section.render_html(
+                &self.tera,
+                &self.config,
+                &self.data,
+                &self.library.read().unwrap(),
+            )?;
             let content = self.inject_livereload(output);
-            self.write_content(&components, "index.html", content, false)?;
+            self.write_content(&components, "index.html", content, false, OutputSource::Section)?;
         }
 
         Ok(())
@@ -1090,6 +1393,7 @@ impl Site {
         &self,
         components: Vec<&'a str>,
         paginator: &'a Paginator,
+        source: OutputSource,
     ) -> Result<()> {
         ensure_directory_exists(&self.output_path)?;
 
@@ -1106,20 +1410,34 @@ impl Site {
                 let output = paginator.render_pager(
                     pager,
                     &self.config,
+                    &self.data,
                     &self.tera,
                     &self.library.read().unwrap(),
                 )?;
                 let content = self.inject_livereload(output);
 
                 if pager.index > 1 {
-                    self.write_content(&pager_components, "index.html", content, false)?;
+                    self.write_content(
+                        &pager_components,
+                        "index.html",
+                        content,
+                        false,
+                        source.clone(),
+                    )?;
                 } else {
-                    self.write_content(&index_components, "index.html", content, false)?;
+                    self.write_content(
+                        &index_components,
+                        "index.html",
+                        content,
+                        false,
+                        source.clone(),
+                    )?;
                     self.write_content(
                         &pager_components,
                         "index.html",
                         render_redirect_template(&paginator.permalink, &self.tera)?,
                         false,
+                        OutputSource::Redirect { target: paginator.permalink.clone() },
                     )?;
                 }
 
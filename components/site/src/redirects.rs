@@ -0,0 +1,230 @@
+use std::fs;
+
+use serde_derive::Serialize;
+use url::Url;
+
+use crate::Site;
+use config::{Config, RedirectFormat};
+use errors::{Error, Result};
+use utils::fs::{create_file, unlink_file};
+
+const HEADER: &str =
+    "Redirects generated by Zola from the `aliases` and `redirect_to` of the content";
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Redirect {
+    /// The path on the server being redirected
+    pub from: String,
+    /// The URL to redirect to
+    pub to: String,
+    pub status: u16,
+}
+
+/// Returns the path on the server of a site-relative path, with the path of the `base_url`
+/// if the site isn't at the root of the domain, as written by `render_alias`
+fn server_path(config: &Config, path: &str) -> String {
+    let base_path = match Url::parse(&config.base_url) {
+        Ok(url) => url.path().trim_end_matches('/').to_string(),
+        Err(_) => String::new(),
+    };
+    let path = path.trim_start_matches('/');
+    if path.is_empty() || path.ends_with('/') || path.ends_with(".html") {
+        format!("{}/{}", base_path, path)
+    } else {
+        format!("{}/{}/", base_path, path)
+    }
+}
+
+/// Finds the redirects of the `aliases` of the pages and sections and of the `redirect_to`
+/// of the sections
+pub fn collect_redirects(site: &Site) -> Vec<Redirect> {
+    let config = &site.config;
+    let library = site.library.read().unwrap();
+    let mut redirects = Vec::new();
+
+    for (_, page) in library.pages() {
+        for alias in &page.meta.aliases {
+            redirects.push(Redirect {
+                from: server_path(config, alias),
+                to: page.permalink.clone(),
+                status: config.redirects.aliases_status,
+            });
+        }
+    }
+    for (_, section) in library.sections() {
+        for alias in &section.meta.aliases {
+            redirects.push(Redirect {
+                from: server_path(config, alias),
+                to: section.permalink.clone(),
+                status: config.redirects.aliases_status,
+            });
+        }
+        // Sections that aren't rendered don't redirect either
+        if let Some(redirect_to) = section.meta.redirect_to.as_ref().filter(|_| section.meta.render)
+        {
+            redirects.push(Redirect {
+                from: server_path(config, &section.path),
+                to: config.make_permalink(redirect_to),
+                status: config.redirects.redirect_to_status,
+            });
+        }
+    }
+
+    redirects.sort();
+    redirects
+}
+
+/// The content of a `_redirects` file, used by Netlify and Cloudflare Pages.
+/// Both match the paths with and without their trailing slash
+pub fn netlify_redirects(redirects: &[Redirect]) -> String {
+    let mut content = format!("# {}\n", HEADER);
+    for redirect in redirects {
+        content.push_str(&format!("{} {} {}\n", redirect.from, redirect.to, redirect.status));
+    }
+    content
+}
+
+/// The content of an nginx config file mapping the paths to redirect to their targets,
+/// with one variable per status code since `return` needs a literal one
+pub fn nginx_redirects(redirects: &[Redirect]) -> String {
+    let mut statuses: Vec<_> = redirects.iter().map(|r| r.status).collect();
+    statuses.sort_unstable();
+    statuses.dedup();
+
+    let mut content = format!("# {}\n", HEADER);
+    content.push_str("# Include this file in the `http` block and add to the `server` block:\n");
+    for status in &statuses {
+        content.push_str(&format!(
+            "#     if ($zola_redirect_{0}) {{ return {0} $zola_redirect_{0}$is_args$args; }}\n",
+            status
+        ));
+    }
+
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    for status in &statuses {
+        content.push_str(&format!("map $uri $zola_redirect_{} {{\n", status));
+        for redirect in redirects.iter().filter(|r| r.status == *status) {
+            content.push_str(&format!("    {} {};\n", quote(&redirect.from), quote(&redirect.to)));
+            if let Some(from) = redirect.from.strip_suffix('/').filter(|f| !f.is_empty()) {
+                content.push_str(&format!("    {} {};\n", quote(from), quote(&redirect.to)));
+            }
+        }
+        content.push_str("}\n");
+    }
+    content
+}
+
+/// The content of an Apache `.htaccess` file, matching the paths with and without their
+/// trailing slash
+pub fn apache_redirects(redirects: &[Redirect]) -> String {
+    let mut content = format!("# {}\n", HEADER);
+    for redirect in redirects {
+        let mut pattern = String::new();
+        for c in redirect.from.trim_end_matches('/').chars() {
+            if ".^$*+?()[]{}|\\".contains(c) {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        if redirect.from.ends_with('/') {
+            pattern.push_str("/?");
+        }
+        content.push_str(&format!(
+            "RedirectMatch {} ^{}$ {}\n",
+            redirect.status, pattern, redirect.to
+        ));
+    }
+    content
+}
+
+/// Writes the redirect files asked for in the config. The redirects are appended to the
+/// `_redirects` and `.htaccess` files copied from the `static` folders, if any
+pub fn write_redirect_files(site: &Site) -> Result<()> {
+    let redirects = collect_redirects(site);
+
+    for format in &site.config.redirects.formats {
+        let (filename, content) = match format {
+            RedirectFormat::Netlify => ("_redirects", netlify_redirects(&redirects)),
+            RedirectFormat::Nginx => ("redirects.map", nginx_redirects(&redirects)),
+            RedirectFormat::Apache => (".htaccess", apache_redirects(&redirects)),
+            RedirectFormat::Json => {
+                ("redirects.json", serde_json::to_string_pretty(&redirects).unwrap())
+            }
+        };
+
+        let path = site.output_path.join(filename);
+        let content = match format {
+            RedirectFormat::Netlify | RedirectFormat::Apache if path.exists() => {
+                let existing = fs::read_to_string(&path).map_err(|e| {
+                    Error::chain(format!("Failed to read {} to add the redirects", filename), e)
+                })?;
+                format!("{}\n{}", existing.trim_end(), content)
+            }
+            _ => content,
+        };
+        unlink_file(&path)?;
+        create_file(&path, &content)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirects() -> Vec<Redirect> {
+        vec![
+            Redirect {
+                from: "/old-page/".to_string(),
+                to: "https://example.com/new-page/".to_string(),
+                status: 301,
+            },
+            Redirect {
+                from: "/docs/v1.html".to_string(),
+                to: "https://example.com/docs/".to_string(),
+                status: 302,
+            },
+        ]
+    }
+
+    #[test]
+    fn can_get_server_paths() {
+        let mut config = Config::default();
+        config.base_url = "https://example.com".to_string();
+        assert_eq!(server_path(&config, "old-page"), "/old-page/");
+        assert_eq!(server_path(&config, "/old-page/"), "/old-page/");
+        assert_eq!(server_path(&config, "docs/v1.html"), "/docs/v1.html");
+        config.base_url = "https://example.com/blog/".to_string();
+        assert_eq!(server_path(&config, "old-page"), "/blog/old-page/");
+    }
+
+    #[test]
+    fn can_write_netlify_redirects() {
+        let content = netlify_redirects(&redirects());
+        assert!(content.starts_with("# Redirects generated by Zola"));
+        assert!(content.contains("/old-page/ https://example.com/new-page/ 301\n"));
+        assert!(content.contains("/docs/v1.html https://example.com/docs/ 302\n"));
+    }
+
+    #[test]
+    fn can_write_nginx_redirects() {
+        let content = nginx_redirects(&redirects());
+        assert!(content.contains(
+            "#     if ($zola_redirect_301) { return 301 $zola_redirect_301$is_args$args; }\n"
+        ));
+        assert!(content.contains(
+            "map $uri $zola_redirect_301 {\n    \"/old-page/\" \"https://example.com/new-page/\";\n    \"/old-page\" \"https://example.com/new-page/\";\n}\n"
+        ));
+        assert!(content.contains(
+            "map $uri $zola_redirect_302 {\n    \"/docs/v1.html\" \"https://example.com/docs/\";\n}\n"
+        ));
+    }
+
+    #[test]
+    fn can_write_apache_redirects() {
+        let content = apache_redirects(&redirects());
+        assert!(content.contains("RedirectMatch 301 ^/old-page/?$ https://example.com/new-page/\n"));
+        assert!(content.contains("RedirectMatch 302 ^/docs/v1\\.html$ https://example.com/docs/\n"));
+    }
+}
@@ -4,12 +4,55 @@ use std::path::{Path, PathBuf};
 use glob::glob;
 use sass_rs::{compile_file, Options, OutputStyle};
 
-use errors::{bail, Result};
-use utils::fs::{create_file, ensure_directory_exists};
+use crate::assets::{minify_asset, write_minified_asset, MinifiedAsset};
+use config::Config;
+use errors::{bail, Error, Result};
+use utils::fs::ensure_directory_exists;
+
+/// The path of every CSS file relative to the output directory with the CSS to write, minified
+/// if the config asks for it, and its source map, if any
+pub type CompiledSass = Vec<(String, MinifiedAsset)>;
+
+/// Compiles the Sass files to the output directory, returning the paths of the CSS files
+/// relative to it
+pub fn compile_sass(base_path: &Path, output_path: &Path, config: &Config) -> Result<Vec<String>> {
+    write_sass(&compile_sass_files(base_path, config)?, output_path)
+}
+
+/// The directories with a `sass` folder to compile: the theme one if it has any Sass,
+/// then the site one if `compile_sass` is set so its files overwrite the theme ones
+pub fn sass_dirs(base_path: &Path, config: &Config) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(ref theme) = config.theme {
+        let theme_path = base_path.join("themes").join(theme);
+        if theme_path.join("sass").exists() {
+            dirs.push(theme_path);
+        }
+    }
+    if config.compile_sass {
+        dirs.push(base_path.to_path_buf());
+    }
+    dirs
+}
 
-pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
+/// Writes the compiled Sass files to the output directory, returning the paths of the CSS files
+/// relative to it
+pub fn write_sass(compiled: &[(String, MinifiedAsset)], output_path: &Path) -> Result<Vec<String>> {
     ensure_directory_exists(output_path)?;
 
+    let mut css_paths = Vec::new();
+    for (path, css) in compiled {
+        let css_output_path = output_path.join(path);
+        create_dir_all(css_output_path.parent().unwrap())?;
+        write_minified_asset(&css_output_path, css)?;
+        css_paths.push(path.clone());
+    }
+
+    Ok(css_paths)
+}
+
+/// Compiles and minifies the Sass files without writing them
+pub fn compile_sass_files(base_path: &Path, config: &Config) -> Result<CompiledSass> {
     let sass_path = {
         let mut sass_path = PathBuf::from(base_path);
         sass_path.push("sass");
@@ -17,13 +60,13 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
     };
 
     let mut options = Options { output_style: OutputStyle::Compressed, ..Default::default() };
-    let mut compiled_paths = compile_sass_glob(&sass_path, output_path, "scss", &options)?;
+    let mut compiled = compile_sass_glob(&sass_path, "scss", &options)?;
 
     options.indented_syntax = true;
-    compiled_paths.extend(compile_sass_glob(&sass_path, output_path, "sass", &options)?);
+    compiled.extend(compile_sass_glob(&sass_path, "sass", &options)?);
 
-    compiled_paths.sort();
-    for window in compiled_paths.windows(2) {
+    compiled.sort();
+    for window in compiled.windows(2) {
         if window[0].1 == window[1].1 {
             bail!(
                 "SASS path conflict: \"{}\" and \"{}\" both compile to \"{}\"",
@@ -34,34 +77,35 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
         }
     }
 
-    Ok(())
+    let mut output = Vec::new();
+    for (_, css_path, css) in compiled {
+        let path = css_path.to_string_lossy().replace('\\', "/");
+        let css = minify_asset(config, &path, &css)
+            .map_err(|e| Error::chain(format!("Failed to minify {}", path), e))?
+            .unwrap_or((css, None));
+        output.push((path, css));
+    }
+
+    Ok(output)
 }
 
 fn compile_sass_glob(
     sass_path: &Path,
-    output_path: &Path,
     extension: &str,
     options: &Options,
-) -> Result<Vec<(PathBuf, PathBuf)>> {
+) -> Result<Vec<(PathBuf, PathBuf, String)>> {
     let files = get_non_partial_scss(sass_path, extension);
 
-    let mut compiled_paths = Vec::new();
+    let mut compiled = Vec::new();
     for file in files {
         let css = compile_file(&file, options.clone())?;
 
         let path_inside_sass = file.strip_prefix(&sass_path).unwrap();
-        let parent_inside_sass = path_inside_sass.parent();
-        let css_output_path = output_path.join(path_inside_sass).with_extension("css");
-
-        if parent_inside_sass.is_some() {
-            create_dir_all(&css_output_path.parent().unwrap())?;
-        }
-
-        create_file(&css_output_path, &css)?;
-        compiled_paths.push((path_inside_sass.to_owned(), css_output_path));
+        let css_path = path_inside_sass.with_extension("css");
+        compiled.push((path_inside_sass.to_owned(), css_path, css));
     }
 
-    Ok(compiled_paths)
+    Ok(compiled)
 }
 
 fn get_non_partial_scss(sass_path: &Path, extension: &str) -> Vec<PathBuf> {
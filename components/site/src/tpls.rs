@@ -4,14 +4,13 @@ use tera::Result as TeraResult;
 
 /// Adds global fns that are to be available to shortcodes while rendering markdown
 pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
-    site.tera.register_filter(
-        "markdown",
-        filters::MarkdownFilter::new(
-            site.base_path.clone(),
-            site.config.clone(),
-            site.permalinks.clone(),
-        )?,
-    );
+    let mut markdown = filters::MarkdownFilter::new(
+        site.base_path.clone(),
+        site.config.clone(),
+        site.permalinks.clone(),
+    )?;
+    markdown.set_wiki_links(site.wiki_link_targets());
+    site.tera.register_filter("markdown", markdown);
     site.tera.register_filter(
         "num_format",
         filters::NumFormatFilter::new(&site.config.default_language),
@@ -43,14 +42,13 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.output_path.clone(),
         ),
     );
-    site.tera.register_function(
-        "load_data",
-        global_fns::LoadData::new(
-            site.base_path.clone(),
-            site.config.theme.clone(),
-            site.output_path.clone(),
-        ),
+    let mut load_data = global_fns::LoadData::new(
+        site.base_path.clone(),
+        site.config.theme.clone(),
+        site.output_path.clone(),
     );
+    load_data.set_config(&site.config.load_data);
+    site.tera.register_function("load_data", load_data);
     site.tera.register_function("trans", global_fns::Trans::new(site.config.clone()));
     site.tera.register_function(
         "get_taxonomy_url",
@@ -82,6 +80,14 @@ pub fn register_tera_global_fns(site: &mut Site) {
         "get_section",
         global_fns::GetSection::new(site.base_path.clone(), site.library.clone()),
     );
+    site.tera.register_function(
+        "get_pages",
+        global_fns::GetPages::new(
+            site.base_path.clone(),
+            site.config.slugify.taxonomies,
+            site.library.clone(),
+        ),
+    );
     site.tera.register_function(
         "get_taxonomy",
         global_fns::GetTaxonomy::new(
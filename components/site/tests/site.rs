@@ -2,12 +2,16 @@ mod common;
 
 use std::collections::HashMap;
 use std::env;
+use std::io::Read;
 use std::path::Path;
 
 use common::{build_site, build_site_with_setup};
-use config::Taxonomy;
+use config::{DarkMode, HighlightTheme, LightDarkThemes, RedirectFormat, Taxonomy};
+use site::fingerprint::fingerprinted_path;
 use site::sitemap;
 use site::Site;
+use tempfile::tempdir;
+use utils::fs::SyncStats;
 
 #[test]
 fn can_parse_site() {
@@ -119,6 +123,9 @@ fn can_build_site_without_live_reload() {
     assert!(file_contains!(public, "posts/python/index.html", "Basic shortcode"));
     assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob"));
     assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob_Sponge"));
+    // The files of the `data` directory are loaded for templates and shortcodes
+    assert!(file_contains!(public, "index.html", "Team: Vincent Bob"));
+    assert!(file_contains!(public, "posts/python/index.html", "Team: 2 members"));
     assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
     assert!(file_exists!(public, "posts/with-assets/index.html"));
     assert!(file_exists!(public, "posts/no-section/simple/index.html"));
@@ -784,3 +791,294 @@ fn check_site() {
 fn current_path(path: &str) -> String {
     format!("[current_path]({})", path)
 }
+
+#[test]
+fn can_fingerprint_assets() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.fingerprint.enabled = true;
+        (site, true)
+    });
+    let manifest = &site.config.fingerprint.manifest;
+
+    // Static files and the Sass output are fingerprinted, other files keep their names
+    let hello = &manifest["scripts/hello.js"];
+    assert!(hello.starts_with("scripts/hello.") && hello.ends_with(".js"));
+    assert!(file_exists!(public, hello));
+    assert!(!file_exists!(public, "scripts/hello.js"));
+    assert!(file_exists!(public, &manifest["site.css"]));
+    assert!(file_exists!(public, &manifest["nested_sass/scss.css"]));
+    assert!(!file_exists!(public, "nested_sass/scss.css"));
+    assert!(file_exists!(public, "robots.txt"));
+    // The hash is the one of the file as written
+    let css = std::fs::read(public.join(&manifest["nested_sass/scss.css"])).unwrap();
+    assert_eq!(fingerprinted_path("nested_sass/scss.css", &css), manifest["nested_sass/scss.css"]);
+
+    // `get_url` returns the fingerprinted URL
+    assert!(file_contains!(
+        public,
+        "index.html",
+        &format!("https://replace-this-with-your-url.com/{}", hello)
+    ));
+    assert!(file_contains!(
+        public,
+        "asset-manifest.json",
+        &format!("\"scripts/hello.js\": \"{}\"", hello)
+    ));
+}
+
+#[test]
+fn can_minify_css_and_js() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.minify_css = true;
+        site.config.minify_js = true;
+        site.config.minify_source_maps = true;
+        (site, true)
+    });
+
+    // Static files
+    assert!(file_contains!(public, "site.css", "body{color:red}"));
+    assert!(file_contains!(public, "site.css", "/*# sourceMappingURL=site.css.map */"));
+    assert!(file_contains!(public, "site.css.map", "\"sources\":[\"site.css\"]"));
+    assert!(!file_contains!(public, "scripts/hello.js", "test content"));
+    assert!(file_contains!(public, "scripts/hello.js", "//# sourceMappingURL=hello.js.map"));
+    assert!(file_contains!(public, "scripts/hello.js.map", "\"sources\":[\"hello.js\"]"));
+
+    // Sass output
+    assert!(file_contains!(public, "nested_sass/scss.css", "sourceMappingURL=scss.css.map"));
+    assert!(file_exists!(public, "nested_sass/scss.css.map"));
+
+    // The static directory itself is left untouched
+    let site_css = std::fs::read_to_string(site.static_path.join("site.css")).unwrap();
+    assert!(site_css.contains("color: red;"));
+}
+
+#[test]
+fn can_regenerate_light_dark_theme_css() {
+    let path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().join("test_site");
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let static_path = tmp_dir.path().to_path_buf();
+    site.static_path = static_path.clone();
+    site.config.markdown.highlight_theme = HighlightTheme::LightDark(LightDarkThemes {
+        light: "base16-ocean-light".to_string(),
+        dark: "base16-ocean-dark".to_string(),
+        filename: "syntax-theme.css".to_string(),
+        dark_mode: DarkMode::Media,
+    });
+    site.render_themes_css().unwrap();
+    assert!(file_contains!(static_path, "syntax-theme.css", "prefers-color-scheme"));
+
+    // Changing the dark mode rewrites the existing stylesheet
+    if let HighlightTheme::LightDark(ref mut themes) = site.config.markdown.highlight_theme {
+        themes.dark_mode = DarkMode::Class;
+    }
+    site.render_themes_css().unwrap();
+    assert!(!file_contains!(static_path, "syntax-theme.css", "prefers-color-scheme"));
+    assert!(file_contains!(static_path, "syntax-theme.css", ":root.dark"));
+}
+
+#[test]
+fn can_fingerprint_minified_assets() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.fingerprint.enabled = true;
+        site.config.minify_css = true;
+        site.config.minify_js = true;
+        (site, true)
+    });
+    let manifest = &site.config.fingerprint.manifest;
+
+    // The hashes are the ones of the minified files as written
+    for path in &["site.css", "scripts/hello.js", "nested_sass/scss.css"] {
+        let content = std::fs::read(public.join(&manifest[*path])).unwrap();
+        assert_eq!(fingerprinted_path(path, &content), manifest[*path]);
+    }
+    assert!(file_contains!(public, &manifest["site.css"], "body{color:red}"));
+}
+
+#[test]
+fn can_compress_output() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.compress_output.enabled = true;
+        site.config.compress_output.brotli = false;
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "index.html.gz"));
+    assert!(!file_exists!(public, "index.html.br"));
+    assert!(file_exists!(public, "sitemap.xml.gz"));
+    // Smaller than `min_size`
+    assert!(!file_exists!(public, "site.css.gz"));
+    // Not one of the compressed extensions
+    assert!(!file_exists!(public, "robots.txt.gz"));
+    assert!(!file_exists!(public, "index.html.gz.gz"));
+
+    let mut decoded = String::new();
+    let gzipped = std::fs::read(public.join("index.html.gz")).unwrap();
+    flate2::read::GzDecoder::new(&gzipped[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, std::fs::read_to_string(public.join("index.html")).unwrap());
+}
+
+#[test]
+fn can_sync_output() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = &tmp_dir.path().join("public");
+    site.set_output_path(public);
+    site.enable_sync_mode();
+    site.load().unwrap();
+
+    let stats = site.build_and_sync().unwrap();
+    assert!(stats.added > 0);
+    assert_eq!((stats.changed, stats.removed), (0, 0));
+    assert!(file_exists!(public, "index.html"));
+    assert!(!tmp_dir.path().join(".public-sync").exists());
+
+    std::fs::write(public.join("index.html"), "outdated").unwrap();
+    std::fs::write(public.join("stale.html"), "stale").unwrap();
+    let stats = site.build_and_sync().unwrap();
+    assert_eq!(stats, SyncStats { added: 0, changed: 1, removed: 1 });
+    assert!(file_contains!(public, "index.html", "Team: Vincent Bob"));
+    assert!(!file_exists!(public, "stale.html"));
+}
+
+#[test]
+fn can_cachebust_urls_when_syncing_output() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.tera
+        .add_raw_template("index.html", r#"{{ get_url(path="blog.css", cachebust=true) }}"#)
+        .unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = &tmp_dir.path().join("public");
+    site.set_output_path(public);
+    site.enable_sync_mode();
+    site.load().unwrap();
+
+    // The compiled Sass is only in the directory being built the first time
+    site.build_and_sync().unwrap();
+    let index = std::fs::read_to_string(public.join("index.html")).unwrap();
+    assert!(index.contains("blog.css?h="));
+
+    // and the hash is the one of the Sass compiled by this build, not of the synced one
+    std::fs::write(public.join("blog.css"), "outdated").unwrap();
+    let stats = site.build_and_sync().unwrap();
+    assert_eq!(stats, SyncStats { added: 0, changed: 1, removed: 0 });
+    assert_eq!(std::fs::read_to_string(public.join("index.html")).unwrap(), index);
+}
+
+#[test]
+fn can_write_build_manifest() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_build_manifest = true;
+        site.config.compress_output.enabled = true;
+        site.config.compress_output.brotli = false;
+        (site, true)
+    });
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(public.join("build-manifest.json")).unwrap())
+            .unwrap();
+    let index = &manifest["index.html"];
+    assert_eq!(index["source"], "section");
+    assert_eq!(index["content_type"], "text/html");
+    assert_eq!(index["size"], std::fs::metadata(public.join("index.html")).unwrap().len());
+    assert_eq!(index["hash"].as_str().unwrap().len(), 64);
+    assert_eq!(manifest["index.html.gz"]["source"], "section");
+    assert_eq!(manifest["index.html.gz"]["encoding"], "gzip");
+    assert_eq!(manifest["posts/simple/index.html"]["source"], "page");
+    assert_eq!(manifest["posts/with-assets/zola.png"]["source"], "page");
+    assert_eq!(manifest["posts/with-assets/zola.png"]["content_type"], "image/png");
+    assert_eq!(manifest["categories/index.html"]["source"], "taxonomy");
+    assert_eq!(manifest["atom.xml"]["source"], "feed");
+    assert_eq!(manifest["sitemap.xml"]["source"], "sitemap");
+    assert_eq!(manifest["site.css"]["source"], "static");
+    assert_eq!(manifest["blog.css"]["source"], "sass");
+    assert_eq!(manifest["robots.txt"]["source"], "other");
+    assert_eq!(manifest["an-old-url/old-page/index.html"]["source"], "alias");
+    assert_eq!(
+        manifest["an-old-url/old-page/index.html"]["target"],
+        "https://replace-this-with-your-url.com/posts/something-else/"
+    );
+    assert_eq!(manifest["posts/page/1/index.html"]["source"], "redirect");
+    assert!(manifest.get("build-manifest.json").is_none());
+}
+
+#[test]
+fn can_write_redirect_files() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.redirects.formats =
+            vec![RedirectFormat::Netlify, RedirectFormat::Nginx, RedirectFormat::Json];
+        site.config.redirects.html = false;
+        site.config.redirects.redirect_to_status = 302;
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/an-old-url/old-page/ https://replace-this-with-your-url.com/posts/something-else/ 301"
+    ));
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/posts/tutorials/devops/ https://replace-this-with-your-url.com/posts/tutorials/devops/docker/ 302"
+    ));
+    assert!(file_contains!(public, "redirects.map", "map $uri $zola_redirect_302 {"));
+    assert!(file_contains!(
+        public,
+        "redirects.map",
+        "\"/an-old-url/old-page\" \"https://replace-this-with-your-url.com/posts/something-else/\";"
+    ));
+    assert!(file_contains!(public, "redirects.json", "\"from\": \"/an-old-url/old-page/\""));
+    assert!(!file_exists!(public, ".htaccess"));
+    // No meta refresh pages when asking for server-side redirects only
+    assert!(!file_exists!(public, "an-old-url/old-page/index.html"));
+    assert!(!file_exists!(public, "posts/tutorials/devops/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/devops/docker/index.html"));
+}
+
+#[test]
+fn can_list_server_side_redirects_in_build_manifest() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_build_manifest = true;
+        site.config.redirects.formats = vec![RedirectFormat::Netlify];
+        site.config.redirects.html = false;
+        (site, true)
+    });
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(public.join("build-manifest.json")).unwrap())
+            .unwrap();
+    let alias = &manifest["an-old-url/old-page/index.html"];
+    assert_eq!(alias["source"], "alias");
+    assert_eq!(alias["target"], "https://replace-this-with-your-url.com/posts/something-else/");
+    assert!(alias.get("hash").is_none());
+    assert_eq!(manifest["top-level.html"]["source"], "alias");
+    let redirect = &manifest["posts/tutorials/devops/index.html"];
+    assert_eq!(redirect["source"], "redirect");
+    assert_eq!(
+        redirect["target"],
+        "https://replace-this-with-your-url.com/posts/tutorials/devops/docker/"
+    );
+}
+
+#[test]
+fn can_use_data_in_feed_and_taxonomy_templates() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        let team = "Team: {% for member in data.team.members %}{{ member.name }} {% endfor %}";
+        site.tera.add_raw_template("atom.xml", team).unwrap();
+        site.tera.add_raw_template("categories/list.html", team).unwrap();
+        site.tera.add_raw_template("categories/single.html", team).unwrap();
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "atom.xml", "Team: Vincent Bob"));
+    assert!(file_contains!(public, "categories/index.html", "Team: Vincent Bob"));
+    assert!(file_contains!(public, "categories/a-category/index.html", "Team: Vincent Bob"));
+    assert!(file_contains!(public, "categories/a-category/atom.xml", "Team: Vincent Bob"));
+}
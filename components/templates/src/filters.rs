@@ -12,11 +12,13 @@ use tera::{
 };
 
 use crate::load_tera;
+use utils::site::WikiLinkTargets;
 
 #[derive(Debug)]
 pub struct MarkdownFilter {
     config: Config,
     permalinks: HashMap<String, String>,
+    wiki_links: WikiLinkTargets,
     tera: Tera,
 }
 
@@ -27,7 +29,13 @@ impl MarkdownFilter {
         permalinks: HashMap<String, String>,
     ) -> TeraResult<Self> {
         let tera = load_tera(&path, &config).map_err(tera::Error::msg)?;
-        Ok(Self { config, permalinks, tera })
+        Ok(Self { config, permalinks, wiki_links: HashMap::new(), tera })
+    }
+
+    /// Sets the pages the `[[wiki links]]` of the rendered markdown can point to by slug or title.
+    /// Without them, only the wiki links to a `.md` path can be resolved.
+    pub fn set_wiki_links(&mut self, wiki_links: WikiLinkTargets) {
+        self.wiki_links = wiki_links;
     }
 }
 
@@ -39,6 +47,7 @@ impl TeraFilter for MarkdownFilter {
         // markdown respecting language preferences.
         let mut context = RenderContext::from_config(&self.config);
         context.permalinks = Cow::Borrowed(&self.permalinks);
+        context.set_wiki_links(&self.wiki_links);
         context.tera = Cow::Borrowed(&self.tera);
         let def = utils::templates::get_shortcodes(&self.tera);
         context.set_shortcode_definitions(&def);
@@ -140,7 +149,7 @@ mod tests {
         let mut tera =
             super::load_tera(&PathBuf::new(), &config).map_err(tera::Error::msg).unwrap();
         tera.add_raw_template("shortcodes/explicitlang.html", "a{{ lang }}a").unwrap();
-        let filter = MarkdownFilter { config, permalinks, tera };
+        let filter = MarkdownFilter { config, permalinks, wiki_links: HashMap::new(), tera };
         let result = filter.filter(&to_value(&"{{ explicitlang(lang='jp') }}").unwrap(), &args);
         println!("{:?}", result);
         assert!(result.is_ok());
@@ -220,6 +229,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn markdown_filter_can_use_wiki_links() {
+        let mut config = Config::default();
+        config.markdown.wiki_links = true;
+        let mut permalinks = HashMap::new();
+        permalinks.insert("blog/hello.md".to_string(), "/foo/blog/hello/".to_string());
+        let mut wiki_links = HashMap::new();
+        wiki_links.insert(
+            "hello world".to_string(),
+            vec![("en".to_string(), "blog/hello.md".to_string())],
+        );
+        let mut filter = MarkdownFilter::new(PathBuf::new(), config, permalinks).unwrap();
+        filter.set_wiki_links(wiki_links);
+
+        let md = "Read [[Hello World]]!";
+        let result = filter.filter(&to_value(md).unwrap(), &HashMap::new());
+        assert!(result.is_ok());
+        assert!(result.unwrap().as_str().unwrap().contains("href=\"/foo/blog/hello/\""));
+    }
+
     #[test]
     fn base64_encode_filter() {
         // from https://tools.ietf.org/html/rfc4648#section-10
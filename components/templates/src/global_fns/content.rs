@@ -1,8 +1,8 @@
-use library::{Library, Taxonomy};
+use library::{sort_pages_by_date, sort_pages_by_title, sort_pages_by_weight, Library, Taxonomy};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
-use tera::{from_value, to_value, Function as TeraFn, Result, Value};
+use tera::{from_value, to_value, Function as TeraFn, Map, Result, Value};
 use utils::slugs::{slugify_paths, SlugifyStrategy};
 
 #[derive(Debug)]
@@ -135,6 +135,210 @@ impl TeraFn for GetSection {
     }
 }
 
+/// Looks up a value of the `extra` front matter, `a.b` being the `b` key of the `a` table
+fn get_extra_value<'a>(extra: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = extra.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+#[derive(Debug)]
+pub struct GetPages {
+    base_path: PathBuf,
+    slugify: SlugifyStrategy,
+    library: Arc<RwLock<Library>>,
+}
+impl GetPages {
+    pub fn new(
+        base_path: PathBuf,
+        slugify: SlugifyStrategy,
+        library: Arc<RwLock<Library>>,
+    ) -> Self {
+        Self { base_path: base_path.join("content"), slugify, library }
+    }
+}
+impl TeraFn for GetPages {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let section =
+            optional_arg!(String, args.get("section"), "`get_pages`: `section` must be a string");
+        let recursive = optional_arg!(
+            bool,
+            args.get("recursive"),
+            "`get_pages`: `recursive` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let taxonomy =
+            optional_arg!(String, args.get("taxonomy"), "`get_pages`: `taxonomy` must be a string");
+        let terms = optional_arg!(
+            Vec<String>,
+            args.get("terms"),
+            "`get_pages`: `terms` must be an array of strings"
+        );
+        let lang = optional_arg!(String, args.get("lang"), "`get_pages`: `lang` must be a string");
+        let extra_key = optional_arg!(
+            String,
+            args.get("extra_key"),
+            "`get_pages`: `extra_key` must be a string"
+        );
+        let extra_value = args.get("extra_value");
+        let sort_by =
+            optional_arg!(String, args.get("sort_by"), "`get_pages`: `sort_by` must be a string")
+                .unwrap_or_else(|| "date".to_string());
+        let reverse = optional_arg!(
+            bool,
+            args.get("reverse"),
+            "`get_pages`: `reverse` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let limit = optional_arg!(
+            usize,
+            args.get("limit"),
+            "`get_pages`: `limit` must be a non-negative integer"
+        );
+
+        let mut dates = Vec::with_capacity(2);
+        for arg in &["after", "before"] {
+            let date = match optional_arg!(
+                String,
+                args.get(*arg),
+                format!("`get_pages`: `{}` must be a date string", arg)
+            ) {
+                Some(d) => match front_matter::parse_datetime(&d) {
+                    Some(dt) => Some(dt),
+                    None => {
+                        return Err(format!(
+                            "`get_pages`: `{}` must be a date like `2021-12-31`, got `{}`",
+                            arg, d
+                        )
+                        .into())
+                    }
+                },
+                None => None,
+            };
+            dates.push(date);
+        }
+        let (after, before) = (dates[0], dates[1]);
+
+        let terms = match (taxonomy, terms) {
+            (Some(taxonomy), Some(terms)) => Some((
+                taxonomy,
+                terms.iter().map(|t| slugify_paths(t, self.slugify)).collect::<Vec<_>>(),
+            )),
+            (None, None) => None,
+            _ => {
+                return Err("`get_pages`: `taxonomy` and `terms` need to be used together"
+                    .to_string()
+                    .into())
+            }
+        };
+        if extra_key.is_some() != extra_value.is_some() {
+            return Err("`get_pages`: `extra_key` and `extra_value` need to be used together"
+                .to_string()
+                .into());
+        }
+
+        let library = self.library.read().unwrap();
+        let candidates: Vec<_> = match section {
+            Some(path) => {
+                let full_path = self.base_path.join(&path);
+                let key = match library.get_section_key(&full_path) {
+                    Some(k) => *k,
+                    None => {
+                        return Err(format!("`get_pages`: section `{}` not found.", path).into())
+                    }
+                };
+                let mut keys = vec![];
+                let mut to_visit = vec![key];
+                while let Some(key) = to_visit.pop() {
+                    let section = library.get_section_by_key(key);
+                    keys.extend(section.pages.iter().chain(section.ignored_pages.iter()).copied());
+                    if recursive {
+                        to_visit.extend(section.subsections.iter().copied());
+                    }
+                }
+                keys.into_iter().map(|k| (k, library.get_page_by_key(k))).collect()
+            }
+            None => library.pages().iter().collect(),
+        };
+
+        let candidates: Vec<_> = candidates
+            .into_iter()
+            .filter(|(_, page)| match &lang {
+                Some(l) => &page.lang == l,
+                None => true,
+            })
+            .filter(|(_, page)| match (after, before, page.meta.datetime) {
+                (None, None, _) => true,
+                (_, _, Some(dt)) => after.iter().all(|a| dt > *a) && before.iter().all(|b| dt < *b),
+                (_, _, None) => false,
+            })
+            .filter(|(_, page)| match &terms {
+                Some((taxonomy, terms)) => page
+                    .meta
+                    .taxonomies
+                    .get(taxonomy)
+                    .into_iter()
+                    .flatten()
+                    .any(|term| terms.contains(&slugify_paths(term, self.slugify))),
+                None => true,
+            })
+            .filter(|(_, page)| match (&extra_key, extra_value) {
+                (Some(key), Some(value)) => get_extra_value(&page.meta.extra, key) == Some(value),
+                _ => true,
+            })
+            .collect();
+
+        let (mut sorted, unsortable) = match sort_by.as_str() {
+            "date" => sort_pages_by_date(
+                candidates.iter().map(|(k, p)| (k, p.meta.datetime, p.permalink.as_str())).collect(),
+            ),
+            "updated" => sort_pages_by_date(
+                candidates
+                    .iter()
+                    .map(|(k, p)| {
+                        (
+                            k,
+                            std::cmp::max(p.meta.datetime, p.meta.updated_datetime),
+                            p.permalink.as_str(),
+                        )
+                    })
+                    .collect(),
+            ),
+            "title" => sort_pages_by_title(
+                candidates
+                    .iter()
+                    .map(|(k, p)| (k, p.meta.title.as_deref(), p.permalink.as_str()))
+                    .collect(),
+            ),
+            "weight" => sort_pages_by_weight(
+                candidates.iter().map(|(k, p)| (k, p.meta.weight, p.permalink.as_str())).collect(),
+            ),
+            "none" => (candidates.iter().map(|(k, _)| *k).collect(), vec![]),
+            _ => {
+                return Err(format!(
+                    "`get_pages`: `sort_by` must be one of `date`, `updated`, `title`, `weight` or `none`, got `{}`",
+                    sort_by
+                )
+                .into())
+            }
+        };
+        if reverse {
+            sorted.reverse();
+        }
+
+        let pages: Vec<_> = sorted
+            .into_iter()
+            .chain(unsortable)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|k| library.get_page_by_key(k).to_serialized_basic(&library))
+            .collect();
+        Ok(to_value(pages).unwrap())
+    }
+}
+
 #[derive(Debug)]
 pub struct GetTaxonomy {
     library: Arc<RwLock<Library>>,
@@ -186,7 +390,7 @@ impl TeraFn for GetTaxonomy {
 mod tests {
     use super::*;
     use config::{Config, Taxonomy as TaxonomyConfig};
-    use library::TaxonomyItem;
+    use library::{Page, Section, TaxonomyItem};
 
     #[test]
     fn can_get_taxonomy() {
@@ -342,4 +546,132 @@ mod tests {
         args.insert("name".to_string(), to_value("random").unwrap());
         assert!(static_fn.call(&args).is_err());
     }
+
+    fn create_library_for_get_pages() -> Arc<RwLock<Library>> {
+        let config = Config::default();
+        let mut library = Library::new(4, 3, false);
+        let sections = ["_index.md", "blog/_index.md", "blog/2021/_index.md"];
+        for path in sections {
+            let section = Section::parse(
+                &PathBuf::from("content").join(path),
+                "+++\n+++",
+                &config,
+                &PathBuf::new(),
+            )
+            .unwrap();
+            library.insert_section(section);
+        }
+        let pages = [
+            ("about.md", "title = \"About\"\nweight = 1"),
+            (
+                "blog/hello.md",
+                "title = \"Hello\"\ndate = 2020-05-01\n[taxonomies]\ntags = [\"Release\"]",
+            ),
+            (
+                "blog/2021/update.md",
+                "title = \"Update\"\ndate = 2021-03-01\n[taxonomies]\ntags = [\"release\", \"rust\"]",
+            ),
+            (
+                "blog/2021/another.md",
+                "title = \"Another\"\ndate = 2021-06-01\n[extra]\nauthor = { name = \"Jane\" }",
+            ),
+        ];
+        for (path, front_matter) in pages {
+            let page = Page::parse(
+                &PathBuf::from("content").join(path),
+                &format!("+++\n{}\n+++", front_matter),
+                &config,
+                &PathBuf::new(),
+            )
+            .unwrap();
+            library.insert_page(page);
+        }
+        library.populate_sections(&config);
+        Arc::new(RwLock::new(library))
+    }
+
+    fn get_pages_titles(static_fn: &GetPages, args: &[(&str, Value)]) -> Vec<String> {
+        let args = args.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+        let res = static_fn.call(&args).unwrap();
+        res.as_array().unwrap().iter().map(|p| p["title"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn can_get_pages() {
+        let static_fn =
+            GetPages::new(PathBuf::new(), SlugifyStrategy::On, create_library_for_get_pages());
+        assert_eq!(get_pages_titles(&static_fn, &[]), vec!["Another", "Update", "Hello", "About"]);
+        assert_eq!(
+            get_pages_titles(&static_fn, &[("section", to_value("blog/_index.md").unwrap())]),
+            vec!["Hello"]
+        );
+        assert_eq!(
+            get_pages_titles(
+                &static_fn,
+                &[
+                    ("section", to_value("blog/_index.md").unwrap()),
+                    ("recursive", to_value(true).unwrap()),
+                    ("reverse", to_value(true).unwrap()),
+                ]
+            ),
+            vec!["Hello", "Update", "Another"]
+        );
+        assert_eq!(
+            get_pages_titles(
+                &static_fn,
+                &[
+                    ("taxonomy", to_value("tags").unwrap()),
+                    ("terms", to_value(["release"]).unwrap()),
+                    ("limit", to_value(1).unwrap()),
+                ]
+            ),
+            vec!["Update"]
+        );
+        assert_eq!(
+            get_pages_titles(
+                &static_fn,
+                &[
+                    ("after", to_value("2020-05-01").unwrap()),
+                    ("before", to_value("2021-06-01").unwrap()),
+                ]
+            ),
+            vec!["Update"]
+        );
+        assert_eq!(
+            get_pages_titles(
+                &static_fn,
+                &[
+                    ("extra_key", to_value("author.name").unwrap()),
+                    ("extra_value", to_value("Jane").unwrap()),
+                ]
+            ),
+            vec!["Another"]
+        );
+        assert_eq!(
+            get_pages_titles(&static_fn, &[("sort_by", to_value("title").unwrap())]),
+            vec!["About", "Another", "Hello", "Update"]
+        );
+        // Pages without a weight come last
+        assert_eq!(
+            get_pages_titles(&static_fn, &[("sort_by", to_value("weight").unwrap())])[0],
+            "About"
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_get_pages_args() {
+        let static_fn =
+            GetPages::new(PathBuf::new(), SlugifyStrategy::On, create_library_for_get_pages());
+        let invalid_args = [
+            ("section", to_value("missing/_index.md").unwrap()),
+            ("taxonomy", to_value("tags").unwrap()),
+            ("after", to_value("yesterday").unwrap()),
+            ("sort_by", to_value("author").unwrap()),
+        ];
+        for (key, value) in invalid_args {
+            let mut args = HashMap::new();
+            args.insert(key.to_string(), value);
+            assert!(static_fn.call(&args).is_err());
+        }
+    }
 }
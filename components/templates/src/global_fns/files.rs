@@ -111,7 +111,16 @@ impl TeraFn for GetUrl {
 
             let path_with_lang = segments.join("/");
 
-            let mut permalink = self.config.make_permalink(&path_with_lang);
+            // Fingerprinted assets are only available under their new name
+            let mut permalink = match self
+                .config
+                .fingerprint
+                .manifest
+                .get(path_with_lang.trim_start_matches('/'))
+            {
+                Some(fingerprinted) => self.config.make_permalink(fingerprinted),
+                None => self.config.make_permalink(&path_with_lang),
+            };
             if !trailing_slash && permalink.ends_with('/') {
                 permalink.pop(); // Removes the slash
             }
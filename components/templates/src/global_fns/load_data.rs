@@ -4,14 +4,18 @@ use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 use csv::Reader;
 use reqwest::header::{HeaderValue, CONTENT_TYPE};
 use reqwest::{blocking::Client, header};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, OpenFlags, ToSql};
+use serde_derive::{Deserialize, Serialize};
 use tera::{from_value, to_value, Error, Function as TeraFn, Map, Result, Value};
 use url::Url;
 use utils::de::fix_toml_dates;
-use utils::fs::{get_file_time, read_file};
+use utils::fs::{create_directory, create_file, get_file_time, read_file};
 
 use crate::global_fns::helpers::search_for_file;
 
@@ -36,12 +40,16 @@ impl FromStr for Method {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum OutputFormat {
     Toml,
     Json,
     Csv,
     Bibtex,
+    Yaml,
+    Xml,
+    Sqlite,
     Plain,
 }
 
@@ -54,6 +62,9 @@ impl FromStr for OutputFormat {
             "csv" => Ok(OutputFormat::Csv),
             "json" => Ok(OutputFormat::Json),
             "bibtex" => Ok(OutputFormat::Bibtex),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "xml" => Ok(OutputFormat::Xml),
+            "sqlite" | "sqlite3" | "db" => Ok(OutputFormat::Sqlite),
             "plain" => Ok(OutputFormat::Plain),
             format => Err(format!("Unknown output format {}", format).into()),
         }
@@ -67,9 +78,44 @@ impl OutputFormat {
             OutputFormat::Csv => "text/csv",
             OutputFormat::Toml => "application/toml",
             OutputFormat::Bibtex => "application/x-bibtex",
+            OutputFormat::Yaml => "application/yaml",
+            OutputFormat::Xml => "application/xml",
+            OutputFormat::Sqlite => "application/vnd.sqlite3",
             OutputFormat::Plain => "text/plain",
         })
     }
+
+    /// Finds the format of a local file from its extension, defaulting to plain text
+    fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|e| OutputFormat::from_str(e.to_str()?).ok())
+            .unwrap_or(OutputFormat::Plain)
+    }
+
+    /// Finds the format of a URL from its extension. Only YAML and XML are recognized
+    /// so the other URLs keep being loaded as plain text if no `format` is given
+    fn from_url(url: &Url) -> Option<Self> {
+        let ext = Path::new(url.path()).extension()?.to_str()?.to_lowercase();
+        match ext.as_ref() {
+            "yaml" | "yml" => Some(OutputFormat::Yaml),
+            "xml" => Some(OutputFormat::Xml),
+            _ => None,
+        }
+    }
+
+    /// Finds the format from the `Content-Type` of a response, e.g. `application/rss+xml`.
+    /// Like for `from_url`, only YAML and XML are recognized
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        match mime.as_ref() {
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(OutputFormat::Yaml)
+            }
+            "application/xml" | "text/xml" => Some(OutputFormat::Xml),
+            m if m.ends_with("+xml") => Some(OutputFormat::Xml),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -116,16 +162,20 @@ impl DataSource {
 
     fn get_cache_key(
         &self,
-        format: &OutputFormat,
+        format: &Option<OutputFormat>,
         method: Method,
         post_body: &Option<String>,
         post_content_type: &Option<String>,
+        query: &Option<String>,
+        params: &Option<Value>,
     ) -> u64 {
         let mut hasher = DefaultHasher::new();
         format.hash(&mut hasher);
         method.hash(&mut hasher);
         post_body.hash(&mut hasher);
         post_content_type.hash(&mut hasher);
+        query.hash(&mut hasher);
+        params.as_ref().map(|p| p.to_string()).hash(&mut hasher);
         self.hash(&mut hasher);
         hasher.finish()
     }
@@ -143,27 +193,31 @@ impl Hash for DataSource {
     }
 }
 
+/// Returns `None` if the format of a URL can't be known before getting the response, it will
+/// then be found from the `Content-Type` of the response
 fn get_output_format_from_args(
     format_arg: Option<String>,
     data_source: &DataSource,
-) -> Result<OutputFormat> {
+) -> Result<Option<OutputFormat>> {
     if let Some(format) = format_arg {
-        return OutputFormat::from_str(&format);
+        return OutputFormat::from_str(&format).map(Some);
     }
 
-    if let DataSource::Path(path) = data_source {
-        match path.extension().and_then(|e| e.to_str()) {
-            Some(ext) => OutputFormat::from_str(ext).or(Ok(OutputFormat::Plain)),
-            None => Ok(OutputFormat::Plain),
-        }
-    } else {
-        // Always default to Plain if we don't know what it is
-        Ok(OutputFormat::Plain)
+    match data_source {
+        DataSource::Path(path) => Ok(Some(OutputFormat::from_path(path))),
+        DataSource::Url(url) => Ok(OutputFormat::from_url(url)),
     }
 }
 
+/// A response kept in the disk cache, parsed again when used
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    format: OutputFormat,
+    data: String,
+}
+
 /// A Tera function to load data from a file or from a URL
-/// Currently the supported formats are json, toml, csv, bibtex and plain text
+/// Currently the supported formats are json, toml, csv, bibtex, yaml, xml, sqlite and plain text
 #[derive(Debug)]
 pub struct LoadData {
     base_path: PathBuf,
@@ -171,6 +225,7 @@ pub struct LoadData {
     client: Arc<Mutex<Client>>,
     result_cache: Arc<Mutex<HashMap<u64, Value>>>,
     output_path: PathBuf,
+    config: config::LoadData,
 }
 impl LoadData {
     pub fn new(base_path: PathBuf, theme: Option<String>, output_path: PathBuf) -> Self {
@@ -181,7 +236,98 @@ impl LoadData {
                 .expect("reqwest client build"),
         ));
         let result_cache = Arc::new(Mutex::new(HashMap::new()));
-        Self { base_path, client, result_cache, theme, output_path }
+        let config = config::LoadData::default();
+        Self { base_path, client, result_cache, theme, output_path, config }
+    }
+
+    /// Uses the `[load_data]` settings of the site for the remote requests: the disk cache,
+    /// the fixtures and the offline mode. Without it, every URL is requested on each build.
+    pub fn set_config(&mut self, config: &config::LoadData) {
+        self.config = config.clone();
+    }
+
+    /// The fixture of a URL is `{fixtures_dir}/{host}/{path}`, without the query string.
+    /// URLs ending with a `/` use the `index` file of the directory.
+    /// The method and the body of the request are not part of it either, so all the requests
+    /// to a URL get the same fixture.
+    fn get_fixture_path(&self, url: &Url) -> Option<PathBuf> {
+        let mut path =
+            self.base_path.join(self.config.fixtures_dir.as_ref()?).join(url.host_str()?);
+        path.extend(url.path_segments()?.filter(|s| !s.is_empty()));
+        if url.path().ends_with('/') {
+            path.push("index");
+        }
+        Some(path)
+    }
+
+    fn get_disk_cache_path(&self, cache_key: u64) -> PathBuf {
+        self.base_path
+            .join(&self.config.cache_dir)
+            .join("load_data")
+            .join(format!("{:x}.json", cache_key))
+    }
+
+    /// Returns the response of a URL from the fixtures, or else from the disk cache if it
+    /// hasn't expired. Expired responses are still used in offline mode.
+    /// Like the URLs themselves, the stored responses can't be SQLite databases.
+    fn get_stored_response(
+        &self,
+        url: &Url,
+        cache_key: u64,
+        format: Option<OutputFormat>,
+    ) -> Result<Option<(String, OutputFormat)>> {
+        if let Some(path) = self.get_fixture_path(url).filter(|p| p.is_file()) {
+            // Fixtures are local files so they get the format of their extension
+            let format = format.unwrap_or_else(|| OutputFormat::from_path(&path));
+            if format == OutputFormat::Sqlite {
+                return Err(format!(
+                    "`load_data`: the fixture {:?} of {} is a SQLite database, which can only be loaded from a path",
+                    path, url
+                )
+                .into());
+            }
+            let data = read_file(&path)
+                .map_err(|e| format!("`load_data`: error reading fixture {:?}: {}", path, e))?;
+            return Ok(Some((data, format)));
+        }
+
+        if !self.config.cache && !self.config.offline {
+            return Ok(None);
+        }
+        let path = self.get_disk_cache_path(cache_key);
+        let modified = match get_file_time(&path) {
+            Some(m) => m,
+            None => return Ok(None),
+        };
+        let expired = match SystemTime::now().duration_since(modified) {
+            Ok(age) => age.as_secs() > self.config.cache_ttl,
+            Err(_) => false,
+        };
+        if expired && !self.config.offline {
+            return Ok(None);
+        }
+
+        let content = read_file(&path)
+            .map_err(|e| format!("`load_data`: error reading cache file {:?}: {}", path, e))?;
+        let response: CachedResponse = serde_json::from_str(&content)
+            .map_err(|e| format!("`load_data`: invalid cache file {:?}: {}", path, e))?;
+        if response.format == OutputFormat::Sqlite {
+            return Err(format!("`load_data`: invalid cache file {:?}: SQLite format", path).into());
+        }
+        Ok(Some((response.data, response.format)))
+    }
+
+    /// Keeps the response in the disk cache, if it is enabled
+    fn store_response(&self, cache_key: u64, data: &str, format: OutputFormat) -> Result<()> {
+        if !self.config.cache {
+            return Ok(());
+        }
+        let path = self.get_disk_cache_path(cache_key);
+        let response = CachedResponse { format, data: data.to_string() };
+        create_directory(path.parent().unwrap())
+            .and_then(|_| create_file(&path, &serde_json::to_string(&response).unwrap()))
+            .map_err(|e| format!("`load_data`: Failed to write cache file {:?}: {}", path, e))?;
+        Ok(())
     }
 }
 
@@ -194,7 +340,7 @@ impl TeraFn for LoadData {
         let format_arg = optional_arg!(
             String,
             args.get("format"),
-            "`load_data`: `format` needs to be an argument with a string value, being one of the supported `load_data` file types (csv, json, toml, bibtex, plain)"
+            "`load_data`: `format` needs to be an argument with a string value, being one of the supported `load_data` file types (csv, json, toml, bibtex, yaml, xml, sqlite, plain)"
         );
         let required = optional_arg!(
             bool,
@@ -215,6 +361,15 @@ impl TeraFn for LoadData {
             args.get("method"),
             "`load_data` method must either be POST or GET."
         );
+        // The SQL query of SQLite databases or a JSONPath expression for the other formats
+        let query_arg =
+            optional_arg!(String, args.get("query"), "`load_data`: `query` must be a string");
+        // SQLite databases only
+        let params_arg = optional_arg!(
+            Value,
+            args.get("params"),
+            "`load_data`: `params` must be an array or an object"
+        );
 
         let method = match method_arg {
             Some(ref method_str) => match Method::from_str(method_str) {
@@ -255,27 +410,80 @@ impl TeraFn for LoadData {
         };
 
         let file_format = get_output_format_from_args(format_arg, &data_source)?;
-        let cache_key =
-            data_source.get_cache_key(&file_format, method, &post_body_arg, &post_content_type);
+        if file_format != Some(OutputFormat::Sqlite) && params_arg.is_some() {
+            return Err("`load_data`: `params` can only be used with SQLite databases".into());
+        }
+        let cache_key = data_source.get_cache_key(
+            &file_format,
+            method,
+            &post_body_arg,
+            &post_content_type,
+            &query_arg,
+            &params_arg,
+        );
 
         let mut cache = self.result_cache.lock().expect("result cache lock");
         if let Some(cached_result) = cache.get(&cache_key) {
             return Ok(cached_result.clone());
         }
 
-        let data = match data_source {
-            DataSource::Path(path) => read_file(&path)
+        // Databases are not text files so they are queried directly
+        if file_format == Some(OutputFormat::Sqlite) {
+            let path = match &data_source {
+                DataSource::Path(path) => path,
+                DataSource::Url(url) => {
+                    return Err(format!(
+                        "`load_data`: SQLite databases can only be loaded from a path, not from {}",
+                        url
+                    )
+                    .into());
+                }
+            };
+            let query = match query_arg {
+                Some(query) => query,
+                None => {
+                    return Err(format!(
+                        "`load_data`: a `query` is required to load data from the SQLite database {:?}",
+                        path
+                    )
+                    .into());
+                }
+            };
+            let result = load_sqlite(path, &query, &params_arg)?;
+            cache.insert(cache_key, result.clone());
+            return Ok(result);
+        }
+
+        let stored_response = match &data_source {
+            DataSource::Url(url) => self.get_stored_response(url, cache_key, file_format)?,
+            DataSource::Path(_) => None,
+        };
+
+        let (data, file_format) = match (data_source, stored_response) {
+            (_, Some(response)) => Ok(response),
+            (DataSource::Path(path), None) => read_file(&path)
+                .map(|data| (data, file_format.unwrap_or(OutputFormat::Plain)))
                 .map_err(|e| format!("`load_data`: error reading file {:?}: {}", path, e)),
-            DataSource::Url(url) => {
+            (DataSource::Url(url), None) if self.config.offline => {
+                if !required {
+                    return Ok(Value::Null);
+                }
+                Err(format!(
+                    "`load_data`: Cannot request {} in offline mode, it isn't in the cache nor in the fixtures",
+                    url
+                ))
+            }
+            (DataSource::Url(url), None) => {
+                let accept = match file_format {
+                    Some(format) => format.as_accept_header(),
+                    None => HeaderValue::from_static("*/*"),
+                };
                 let response_client = self.client.lock().expect("response client lock");
                 let req = match method {
-                    Method::Get => response_client
-                        .get(url.as_str())
-                        .header(header::ACCEPT, file_format.as_accept_header()),
+                    Method::Get => response_client.get(url.as_str()).header(header::ACCEPT, accept),
                     Method::Post => {
-                        let mut resp = response_client
-                            .post(url.as_str())
-                            .header(header::ACCEPT, file_format.as_accept_header());
+                        let mut resp =
+                            response_client.post(url.as_str()).header(header::ACCEPT, accept);
                         if let Some(content_type) = post_content_type {
                             match HeaderValue::from_str(&content_type) {
                                 Ok(c) => {
@@ -298,9 +506,20 @@ impl TeraFn for LoadData {
                 };
 
                 match req.send().and_then(|res| res.error_for_status()) {
-                    Ok(r) => r.text().map_err(|e| {
-                        format!("`load_data`: Failed to parse response from {}: {:?}", url, e)
-                    }),
+                    Ok(r) => {
+                        // Always default to Plain if we don't know what it is
+                        let format = file_format
+                            .or_else(|| {
+                                let content_type = r.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+                                OutputFormat::from_content_type(content_type)
+                            })
+                            .unwrap_or(OutputFormat::Plain);
+                        let data = r.text().map_err(|e| {
+                            format!("`load_data`: Failed to parse response from {}: {:?}", url, e)
+                        })?;
+                        self.store_response(cache_key, &data, format)?;
+                        Ok((data, format))
+                    }
                     Err(e) => {
                         if !required {
                             // HTTP error is discarded (because required=false) and
@@ -321,12 +540,11 @@ impl TeraFn for LoadData {
             }
         }?;
 
-        let result_value: Result<Value> = match file_format {
-            OutputFormat::Toml => load_toml(data),
-            OutputFormat::Csv => load_csv(data),
-            OutputFormat::Json => load_json(data),
-            OutputFormat::Bibtex => load_bibtex(data),
-            OutputFormat::Plain => to_value(data).map_err(|e| e.into()),
+        let result_value = parse_data(data, file_format);
+        // Only the selected values are kept in the cache
+        let result_value = match (result_value, query_arg) {
+            (Ok(value), Some(query)) => select_json_path(&value, &query),
+            (result, _) => result,
         };
 
         if let Ok(data_result) = &result_value {
@@ -344,6 +562,237 @@ fn load_json(json_data: String) -> Result<Value> {
     Ok(json_content)
 }
 
+/// Parse a YAML string and convert it to a Tera Value
+fn load_yaml(yaml_data: String) -> Result<Value> {
+    let yaml_content: Value =
+        serde_yaml::from_str(yaml_data.as_str()).map_err(|e| format!("{:?}", e))?;
+    Ok(yaml_content)
+}
+
+/// Converts an XML element to a Tera Value, see `load_xml`
+fn xml_element_to_value(node: roxmltree::Node) -> Value {
+    let mut map = Map::new();
+    for attribute in node.attributes() {
+        map.insert(format!("@{}", attribute.name()), Value::String(attribute.value().to_string()));
+    }
+
+    let mut text = String::new();
+    for child in node.children() {
+        if child.is_text() {
+            text.push_str(child.text().unwrap_or_default());
+        } else if child.is_element() {
+            let name = xml_element_name(child);
+            let value = xml_element_to_value(child);
+            match map.get_mut(&name) {
+                Some(Value::Array(values)) => values.push(value),
+                Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+                None => {
+                    map.insert(name, value);
+                }
+            }
+        }
+    }
+
+    let text = text.trim();
+    if map.is_empty() {
+        return Value::String(text.to_string());
+    }
+    if !text.is_empty() {
+        map.insert("#text".to_string(), Value::String(text.to_string()));
+    }
+    Value::Object(map)
+}
+
+/// The name of an element, with its namespace prefix if it has one, e.g. `atom:link`
+fn xml_element_name(node: roxmltree::Node) -> String {
+    let tag_name = node.tag_name();
+    match tag_name.namespace().and_then(|ns| node.lookup_prefix(ns)) {
+        Some(prefix) => format!("{}:{}", prefix, tag_name.name()),
+        None => tag_name.name().to_string(),
+    }
+}
+
+/// Parse a XML string and convert it to a Tera Value
+///
+/// An example xml file `example.xml` could be:
+/// ```xml
+/// <books updated="2021-12-01">
+///     <book lang="en">Gutenberg</book>
+///     <book>Printing</book>
+/// </books>
+/// ```
+/// The json value output would be:
+/// ```json
+/// {
+///     "books": {
+///         "@updated": "2021-12-01",
+///         "book": [
+///             {"@lang": "en", "#text": "Gutenberg"},
+///             "Printing"
+///         ]
+///     }
+/// }
+/// ```
+fn load_xml(xml_data: String) -> Result<Value> {
+    let document = roxmltree::Document::parse(&xml_data).map_err(|e| format!("{:?}", e))?;
+    let root = document.root_element();
+    let mut xml_map = Map::new();
+    xml_map.insert(xml_element_name(root), xml_element_to_value(root));
+    Ok(Value::Object(xml_map))
+}
+
+/// Parses the content of a file or a response in the given format
+fn parse_data(data: String, format: OutputFormat) -> Result<Value> {
+    match format {
+        OutputFormat::Toml => load_toml(data),
+        OutputFormat::Csv => load_csv(data),
+        OutputFormat::Json => load_json(data),
+        OutputFormat::Bibtex => load_bibtex(data),
+        OutputFormat::Yaml => load_yaml(data),
+        OutputFormat::Xml => load_xml(data),
+        // Databases are queried from their path before reading anything
+        OutputFormat::Sqlite => {
+            Err("`load_data`: SQLite databases can only be loaded from a path".into())
+        }
+        OutputFormat::Plain => to_value(data).map_err(|e| e.into()),
+    }
+}
+
+/// Loads all the toml, json, yaml, csv, bibtex and xml files of a directory, eg the `data`
+/// directory of a site, in an object mirroring the tree of the directory:
+/// `data/team/members.yaml` is loaded as `team.members`.
+/// Hidden files and files in other formats are ignored.
+pub fn load_data_directory(path: &Path) -> Result<Value> {
+    let mut data = Map::new();
+    if !path.is_dir() {
+        return Ok(Value::Object(data));
+    }
+
+    let mut entries = std::fs::read_dir(path)
+        .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read the directory {:?}: {}", path, e))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let entry_path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let (key, value) = if entry_path.is_dir() {
+            (name, load_data_directory(&entry_path)?)
+        } else {
+            let format =
+                match entry_path.extension().and_then(|e| OutputFormat::from_str(e.to_str()?).ok())
+                {
+                    Some(OutputFormat::Sqlite) | Some(OutputFormat::Plain) | None => continue,
+                    Some(format) => format,
+                };
+            let content = read_file(&entry_path)
+                .map_err(|e| format!("Failed to read {:?}: {}", entry_path, e))?;
+            let value = parse_data(content, format)
+                .map_err(|e| format!("Failed to load {:?}: {}", entry_path, e))?;
+            (entry_path.file_stem().unwrap().to_string_lossy().into_owned(), value)
+        };
+
+        if data.contains_key(&key) {
+            return Err(format!("Several files of {:?} would be loaded as `{}`", path, key).into());
+        }
+        data.insert(key, value);
+    }
+
+    Ok(Value::Object(data))
+}
+
+/// Returns the array of the values matching a JSONPath expression, e.g. `$.releases[?(@.stable)]`
+fn select_json_path(value: &Value, query: &str) -> Result<Value> {
+    let selected = jsonpath_lib::select(value, query)
+        .map_err(|e| format!("`load_data`: invalid JSONPath `query` {}: {:?}", query, e))?;
+    Ok(Value::Array(selected.into_iter().cloned().collect()))
+}
+
+/// Converts a value of the `params` of `load_data` to a SQLite value
+fn to_sql_value(value: &Value) -> Result<SqlValue> {
+    match value {
+        Value::Null => Ok(SqlValue::Null),
+        Value::Bool(b) => Ok(SqlValue::Integer(*b as i64)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(SqlValue::Integer(i)),
+            None => Ok(SqlValue::Real(n.as_f64().unwrap_or_default())),
+        },
+        Value::String(s) => Ok(SqlValue::Text(s.clone())),
+        _ => Err(format!(
+            "`load_data`: `params` can only contain strings, numbers, booleans or null, got {}",
+            value
+        )
+        .into()),
+    }
+}
+
+/// Converts a column of a SQLite row to a Tera Value, blobs being encoded in base64
+fn sql_value_to_value(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => Value::String(base64::encode(b)),
+    }
+}
+
+/// Runs a query on a SQLite database opened read-only and returns the rows as an array
+/// of objects keyed by column name.
+/// `params` are bound by position if they are an array or by name if they are an object,
+/// the names being prefixed by `:` unless they already start with `:`, `@` or `$`
+fn load_sqlite(path: &Path, query: &str, params: &Option<Value>) -> Result<Value> {
+    let sql_error = |e: rusqlite::Error| -> Error {
+        format!("`load_data`: error querying the SQLite database {:?}: {}", path, e).into()
+    };
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(sql_error)?;
+    let mut stmt = conn.prepare(query).map_err(sql_error)?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+    let mut rows = match params {
+        None => stmt.query(rusqlite::NO_PARAMS),
+        Some(Value::Array(values)) => {
+            let values = values.iter().map(to_sql_value).collect::<Result<Vec<_>>>()?;
+            stmt.query(values)
+        }
+        Some(Value::Object(map)) => {
+            let mut named = Vec::with_capacity(map.len());
+            for (name, value) in map {
+                let name = if name.starts_with(&[':', '@', '$'][..]) {
+                    name.clone()
+                } else {
+                    format!(":{}", name)
+                };
+                named.push((name, to_sql_value(value)?));
+            }
+            let named: Vec<(&str, &dyn ToSql)> =
+                named.iter().map(|(name, value)| (name.as_str(), value as &dyn ToSql)).collect();
+            stmt.query_named(&named)
+        }
+        Some(_) => return Err("`load_data`: `params` must be an array or an object".into()),
+    }
+    .map_err(sql_error)?;
+
+    let mut records = Vec::new();
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let mut record = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            record.insert(column.clone(), sql_value_to_value(row.get_raw(i)));
+        }
+        records.push(Value::Object(record));
+    }
+
+    Ok(Value::Array(records))
+}
+
 /// Parse a TOML string and convert it to a Tera Value
 fn load_toml(toml_data: String) -> Result<Value> {
     let toml_content: toml::Value = toml::from_str(&toml_data).map_err(|e| format!("{:?}", e))?;
@@ -656,16 +1105,20 @@ mod tests {
     fn calculates_cache_key_for_path() {
         // We can't test against a fixed value, due to the fact the cache key is built from the absolute path
         let cache_key = DataSource::Path(get_test_file("test.toml")).get_cache_key(
-            &OutputFormat::Toml,
+            &Some(OutputFormat::Toml),
             Method::Get,
             &None,
             &None,
+            &None,
+            &None,
         );
         let cache_key_2 = DataSource::Path(get_test_file("test.toml")).get_cache_key(
-            &OutputFormat::Toml,
+            &Some(OutputFormat::Toml),
             Method::Get,
             &None,
             &None,
+            &None,
+            &None,
         );
         assert_eq!(cache_key, cache_key_2);
     }
@@ -673,16 +1126,20 @@ mod tests {
     #[test]
     fn different_cache_key_per_filename() {
         let toml_cache_key = DataSource::Path(get_test_file("test.toml")).get_cache_key(
-            &OutputFormat::Toml,
+            &Some(OutputFormat::Toml),
             Method::Get,
             &None,
             &None,
+            &None,
+            &None,
         );
         let json_cache_key = DataSource::Path(get_test_file("test.json")).get_cache_key(
-            &OutputFormat::Toml,
+            &Some(OutputFormat::Toml),
             Method::Get,
             &None,
             &None,
+            &None,
+            &None,
         );
         assert_ne!(toml_cache_key, json_cache_key);
     }
@@ -690,16 +1147,20 @@ mod tests {
     #[test]
     fn different_cache_key_per_format() {
         let toml_cache_key = DataSource::Path(get_test_file("test.toml")).get_cache_key(
-            &OutputFormat::Toml,
+            &Some(OutputFormat::Toml),
             Method::Get,
             &None,
             &None,
+            &None,
+            &None,
         );
         let json_cache_key = DataSource::Path(get_test_file("test.toml")).get_cache_key(
-            &OutputFormat::Json,
+            &Some(OutputFormat::Json),
             Method::Get,
             &None,
             &None,
+            &None,
+            &None,
         );
         assert_ne!(toml_cache_key, json_cache_key);
     }
@@ -940,6 +1401,152 @@ mod tests {
         )
     }
 
+    #[test]
+    fn can_load_yaml() {
+        let static_fn = LoadData::new(PathBuf::from("../utils/test-files"), None, PathBuf::new());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("test.yaml").unwrap());
+        let result = static_fn.call(&args.clone()).unwrap();
+
+        assert_eq!(
+            result,
+            json!({
+                "key": "value",
+                "array": [1, 2, 3],
+                "subpackage": {
+                    "subkey": 5
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn can_load_xml() {
+        let static_fn = LoadData::new(PathBuf::from("../utils/test-files"), None, PathBuf::new());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("test.xml").unwrap());
+        let result = static_fn.call(&args.clone()).unwrap();
+
+        assert_eq!(
+            result,
+            json!({
+                "rss": {
+                    "@version": "2.0",
+                    "channel": {
+                        "title": "Zola",
+                        "atom:link": {"@href": "https://www.getzola.org/atom.xml", "@rel": "self"},
+                        "item": [
+                            {"title": "First"},
+                            {"title": {"@lang": "en", "#text": "Second"}}
+                        ]
+                    }
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn can_detect_remote_data_format() {
+        let _myaml = mock("GET", "/data.yml")
+            .match_header("accept", "application/yaml")
+            .with_body("key: value")
+            .create();
+        let _mxml = mock("GET", "/feed")
+            .match_header("accept", "*/*")
+            .with_header("content-type", "application/rss+xml; charset=utf-8")
+            .with_body("<rss><title>Zola</title></rss>")
+            .create();
+        let _mplain = mock("GET", "/unknown")
+            .with_header("content-type", "application/octet-stream")
+            .with_body("key: value")
+            .create();
+        // Other formats are only parsed if the `format` argument is given, as they used to be
+        let _mjson_ext = mock("GET", "/data.json").with_body(r#"{"key": "value"}"#).create();
+        let _mjson_type = mock("GET", "/api")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"key": "value"}"#)
+            .create();
+
+        let static_fn = LoadData::new(PathBuf::new(), None, PathBuf::new());
+        let call = |path: &str| {
+            let mut args = HashMap::new();
+            let url = format!("{}{}", mockito::server_url(), path);
+            args.insert("url".to_string(), to_value(&url).unwrap());
+            static_fn.call(&args).unwrap()
+        };
+        assert_eq!(call("/data.yml"), json!({"key": "value"}));
+        assert_eq!(call("/feed"), json!({"rss": {"title": "Zola"}}));
+        assert_eq!(call("/unknown"), json!("key: value"));
+        assert_eq!(call("/data.json"), json!(r#"{"key": "value"}"#));
+        assert_eq!(call("/api"), json!(r#"{"key": "value"}"#));
+    }
+
+    #[test]
+    fn can_use_fixtures_instead_of_requesting_urls() {
+        let dir = tempdir().unwrap();
+        let fixtures = dir.path().join("fixtures").join("example.com");
+        create_dir_all(fixtures.join("api")).unwrap();
+        copy(get_test_file("test.json"), fixtures.join("api").join("data.json")).unwrap();
+        copy(get_test_file("test.toml"), fixtures.join("api").join("index")).unwrap();
+
+        let mut static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+        static_fn.set_config(&config::LoadData {
+            fixtures_dir: Some("fixtures".to_string()),
+            offline: true,
+            ..config::LoadData::default()
+        });
+        let mut args = HashMap::new();
+        args.insert("url".to_string(), to_value("https://example.com/api/data.json?v=1").unwrap());
+        let result = static_fn.call(&args).unwrap();
+        assert_eq!(result["key"], json!("value"));
+
+        let mut args = HashMap::new();
+        args.insert("url".to_string(), to_value("https://example.com/api/").unwrap());
+        args.insert("format".to_string(), to_value("toml").unwrap());
+        let result = static_fn.call(&args).unwrap();
+        assert_eq!(result["category"]["date"], json!("1979-05-27T07:32:00Z"));
+    }
+
+    #[test]
+    fn can_cache_remote_data_on_disk() {
+        let _m = mock("GET", "/ohgh2Iexa8")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"test": "value"}"#)
+            .expect(1)
+            .create();
+        let url = format!("{}{}", mockito::server_url(), "/ohgh2Iexa8");
+        let dir = tempdir().unwrap();
+        let config = config::LoadData { cache: true, ..config::LoadData::default() };
+        let mut args = HashMap::new();
+        args.insert("url".to_string(), to_value(&url).unwrap());
+        args.insert("format".to_string(), to_value("json").unwrap());
+
+        let mut static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+        static_fn.set_config(&config);
+        assert_eq!(static_fn.call(&args).unwrap(), json!({"test": "value"}));
+        assert!(dir.path().join(".zola-cache").join("load_data").exists());
+
+        // A new function doesn't have the data in memory, it can only come from the disk
+        let mut static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+        static_fn.set_config(&config::LoadData { offline: true, ..config });
+        assert_eq!(static_fn.call(&args).unwrap(), json!({"test": "value"}));
+        _m.assert();
+    }
+
+    #[test]
+    fn fails_on_uncached_url_in_offline_mode() {
+        let dir = tempdir().unwrap();
+        let mut static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+        static_fn.set_config(&config::LoadData { offline: true, ..config::LoadData::default() });
+        let mut args = HashMap::new();
+        args.insert("url".to_string(), to_value("https://example.com/data.json").unwrap());
+        let result = static_fn.call(&args);
+        assert!(result.unwrap_err().to_string().contains("in offline mode"));
+
+        args.insert("required".to_string(), to_value(false).unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), tera::Value::Null);
+    }
+
     #[test]
     fn is_load_remote_data_using_post_method_with_different_body_not_cached() {
         let _mjson = mock("POST", "/kr1zdgbm4y3")
@@ -1002,4 +1609,145 @@ mod tests {
 
         _mjson.assert();
     }
+
+    #[test]
+    fn errors_on_sqlite_fixture() {
+        let dir = tempdir().unwrap();
+        let fixtures = dir.path().join("fixtures").join("example.com");
+        create_dir_all(&fixtures).unwrap();
+        create_test_database(&fixtures);
+
+        let mut static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+        static_fn.set_config(&config::LoadData {
+            fixtures_dir: Some("fixtures".to_string()),
+            offline: true,
+            ..config::LoadData::default()
+        });
+        let mut args = HashMap::new();
+        args.insert("url".to_string(), to_value("https://example.com/catalog.db").unwrap());
+        let err = static_fn.call(&args).unwrap_err();
+        assert!(format!("{}", err).contains("is a SQLite database"));
+    }
+
+    fn create_test_database(dir: &std::path::Path) {
+        let conn = rusqlite::Connection::open(dir.join("catalog.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE books (title TEXT, year INTEGER, price REAL, cover BLOB);
+            INSERT INTO books VALUES ('Zola', 2021, 9.5, x'7a6f6c61');
+            INSERT INTO books VALUES ('Tera', 2019, NULL, NULL);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn can_load_sqlite() {
+        let dir = tempdir().unwrap();
+        create_test_database(dir.path());
+        let static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("catalog.db").unwrap());
+        args.insert("query".to_string(), to_value("SELECT * FROM books ORDER BY year").unwrap());
+        assert_eq!(
+            static_fn.call(&args).unwrap(),
+            json!([
+                {"title": "Tera", "year": 2019, "price": null, "cover": null},
+                {"title": "Zola", "year": 2021, "price": 9.5, "cover": "em9sYQ=="},
+            ])
+        );
+
+        args.insert(
+            "query".to_string(),
+            to_value("SELECT title FROM books WHERE year > ? AND title != ?").unwrap(),
+        );
+        args.insert("params".to_string(), json!([2000, "Tera"]));
+        assert_eq!(static_fn.call(&args).unwrap(), json!([{"title": "Zola"}]));
+
+        args.insert(
+            "query".to_string(),
+            to_value("SELECT title FROM books WHERE year < :year").unwrap(),
+        );
+        args.insert("params".to_string(), json!({"year": 2020}));
+        assert_eq!(static_fn.call(&args).unwrap(), json!([{"title": "Tera"}]));
+    }
+
+    #[test]
+    fn sqlite_database_requires_a_query_and_is_read_only() {
+        let dir = tempdir().unwrap();
+        create_test_database(dir.path());
+        let static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("catalog.db").unwrap());
+        let err = static_fn.call(&args).unwrap_err();
+        assert!(err.to_string().contains("a `query` is required"));
+
+        args.insert("query".to_string(), to_value("DELETE FROM books").unwrap());
+        let err = static_fn.call(&args).unwrap_err();
+        assert!(err.to_string().contains("readonly database"));
+    }
+
+    #[test]
+    fn can_select_values_with_json_path() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("releases.json"),
+            r#"{"name": "zola", "releases": [
+                {"version": "0.15.0", "stable": true},
+                {"version": "0.16.0-rc1"},
+                {"version": "0.14.1", "stable": true}
+            ]}"#,
+        )
+        .unwrap();
+        let static_fn = LoadData::new(dir.path().to_path_buf(), None, PathBuf::new());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("releases.json").unwrap());
+        args.insert("query".to_string(), to_value("$.name").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), json!(["zola"]));
+
+        args.insert("query".to_string(), to_value("$.releases[?(@.stable)].version").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), json!(["0.15.0", "0.14.1"]));
+
+        args.insert("query".to_string(), to_value("$.releases[?(@.stable)]").unwrap());
+        assert_eq!(
+            static_fn.call(&args).unwrap(),
+            json!([{"version": "0.15.0", "stable": true}, {"version": "0.14.1", "stable": true}])
+        );
+
+        args.insert("query".to_string(), to_value("$.[").unwrap());
+        let err = static_fn.call(&args).unwrap_err();
+        assert!(err.to_string().contains("invalid JSONPath `query`"));
+    }
+
+    #[test]
+    fn errors_on_params_without_sqlite_database() {
+        let static_fn = LoadData::new(PathBuf::from("../utils"), None, PathBuf::new());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("test-files/test.json").unwrap());
+        args.insert("params".to_string(), json!([1]));
+        let err = static_fn.call(&args).unwrap_err();
+        assert!(err.to_string().contains("can only be used with SQLite databases"));
+    }
+
+    #[test]
+    fn can_load_data_directory() {
+        let dir = tempdir().unwrap();
+        create_dir_all(dir.path().join("team")).unwrap();
+        std::fs::write(dir.path().join("nav.yaml"), "- title: Home\n  url: /\n").unwrap();
+        std::fs::write(dir.path().join("team").join("members.toml"), "lead = \"Vincent\"").unwrap();
+        std::fs::write(dir.path().join("team").join("notes.txt"), "Not loaded").unwrap();
+        std::fs::write(dir.path().join(".hidden.json"), "{").unwrap();
+
+        assert_eq!(
+            super::load_data_directory(dir.path()).unwrap(),
+            json!({
+                "nav": [{"title": "Home", "url": "/"}],
+                "team": {"members": {"lead": "Vincent"}},
+            })
+        );
+
+        std::fs::write(dir.path().join("team.json"), "{}").unwrap();
+        let err = super::load_data_directory(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("would be loaded as `team`"));
+    }
 }
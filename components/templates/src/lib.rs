@@ -24,6 +24,12 @@ lazy_static! {
                 include_str!("builtins/split_sitemap_index.xml"),
             ),
             ("__zola_builtins/anchor-link.html", include_str!("builtins/anchor-link.html")),
+            ("__zola_builtins/admonition.html", include_str!("builtins/admonition.html")),
+            ("__zola_builtins/toc.html", include_str!("builtins/toc.html")),
+            (
+                "__zola_builtins/responsive-image.html",
+                include_str!("builtins/responsive-image.html"),
+            ),
             (
                 "__zola_builtins/shortcodes/youtube.html",
                 include_str!("builtins/shortcodes/youtube.html"),
@@ -37,7 +43,12 @@ lazy_static! {
                 "__zola_builtins/shortcodes/streamable.html",
                 include_str!("builtins/shortcodes/streamable.html"),
             ),
+            (
+                "__zola_builtins/shortcodes/figure.html",
+                include_str!("builtins/shortcodes/figure.html"),
+            ),
             ("internal/alias.html", include_str!("builtins/internal/alias.html")),
+            ("internal/toc_macros.html", include_str!("builtins/internal/toc_macros.html")),
         ])
         .unwrap();
         tera.register_filter("base64_encode", filters::base64_encode);
@@ -1,7 +1,8 @@
 use filetime::{set_file_mtime, FileTime};
-use std::fs::{copy, create_dir_all, metadata, File};
+use std::collections::HashSet;
+use std::fs::{copy, create_dir_all, metadata, read, remove_dir_all, remove_file, rename, File};
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use walkdir::WalkDir;
 
@@ -26,6 +27,16 @@ pub fn create_file(path: &Path, content: &str) -> Result<()> {
     Ok(())
 }
 
+/// Removes the file at the given path if there is one, so that writing or moving a new file
+/// there doesn't modify the file it might be hard linked to, like the ones in `static`
+pub fn unlink_file(path: &Path) -> Result<()> {
+    if path.exists() {
+        remove_file(path)
+            .map_err(|e| Error::chain(format!("Failed to remove {}", path.display()), e))?;
+    }
+    Ok(())
+}
+
 /// Create a directory at the given path if it doesn't exist already
 pub fn ensure_directory_exists(path: &Path) -> Result<()> {
     if !path.exists() {
@@ -145,6 +156,97 @@ pub fn copy_directory(src: &Path, dest: &Path, hard_link: bool) -> Result<()> {
     Ok(())
 }
 
+/// The number of files `sync_directory` added to, changed in and removed from the destination
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+}
+
+/// Makes `dest` identical to `src` by moving the files of `src` that are new or whose content
+/// is different, and by removing what is not in `src` anymore. The files that didn't change
+/// are left as they are, keeping their modification times.
+/// Both directories need to be on the same filesystem.
+pub fn sync_directory(src: &Path, dest: &Path) -> Result<SyncStats> {
+    let mut stats = SyncStats::default();
+    let mut src_files = HashSet::new();
+    let mut src_dirs = HashSet::new();
+    for entry in WalkDir::new(src).min_depth(1).into_iter().filter_map(std::result::Result::ok) {
+        let relative_path = entry.path().strip_prefix(src).unwrap().to_path_buf();
+        if entry.file_type().is_dir() {
+            src_dirs.insert(relative_path);
+        } else {
+            src_files.insert(relative_path);
+        }
+    }
+
+    // Removing what is not generated anymore first, in case a file became a directory
+    let mut stale_dirs: Vec<PathBuf> = Vec::new();
+    for entry in WalkDir::new(dest).min_depth(1).into_iter().filter_map(std::result::Result::ok) {
+        let relative_path = entry.path().strip_prefix(dest).unwrap();
+        if stale_dirs.iter().any(|d| relative_path.starts_with(d)) {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            if !src_dirs.contains(relative_path) {
+                stats.removed += WalkDir::new(entry.path())
+                    .into_iter()
+                    .filter_map(std::result::Result::ok)
+                    .filter(|e| !e.file_type().is_dir())
+                    .count();
+                remove_dir_all(entry.path()).map_err(|e| {
+                    Error::chain(format!("Failed to remove {}", entry.path().display()), e)
+                })?;
+                stale_dirs.push(relative_path.to_path_buf());
+            }
+        } else if !src_files.contains(relative_path) {
+            remove_file(entry.path()).map_err(|e| {
+                Error::chain(format!("Failed to remove {}", entry.path().display()), e)
+            })?;
+            stats.removed += 1;
+        }
+    }
+
+    for relative_path in &src_files {
+        let src_path = src.join(relative_path);
+        let dest_path = dest.join(relative_path);
+        if dest_path.is_file() {
+            let unchanged = metadata(&src_path)?.len() == metadata(&dest_path)?.len()
+                && read(&src_path)? == read(&dest_path)?;
+            if unchanged {
+                continue;
+            }
+            unlink_file(&dest_path)?;
+            stats.changed += 1;
+        } else {
+            stats.added += 1;
+        }
+
+        if let Some(parent_directory) = dest_path.parent() {
+            create_dir_all(parent_directory).map_err(|e| {
+                Error::chain(
+                    format!("Was not able to create folder {}", parent_directory.display()),
+                    e,
+                )
+            })?;
+        }
+        rename(&src_path, &dest_path).map_err(|e| {
+            Error::chain(
+                format!("Was not able to move {} to {}", src_path.display(), dest_path.display()),
+                e,
+            )
+        })?;
+    }
+
+    // Empty directories are part of the output as well
+    for relative_path in &src_dirs {
+        create_dir_all(dest.join(relative_path))?;
+    }
+
+    Ok(stats)
+}
+
 pub fn get_file_time(path: &Path) -> Option<SystemTime> {
     path.metadata().ok().and_then(|meta| {
         Some(match (meta.created().ok(), meta.modified().ok()) {
@@ -178,14 +280,14 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::fs::{metadata, read_to_string, File};
+    use std::fs::{create_dir_all, metadata, read_to_string, File};
     use std::io::Write;
     use std::path::PathBuf;
     use std::str::FromStr;
 
     use tempfile::tempdir_in;
 
-    use super::copy_file;
+    use super::{copy_file, create_file, sync_directory, unlink_file, SyncStats};
 
     #[test]
     fn test_copy_file_timestamp_preserved() {
@@ -250,4 +352,54 @@ mod tests {
         assert_eq!(read_to_string(&src_file_path).unwrap(), "file1");
         assert_eq!(read_to_string(&dest_file_path).unwrap(), "file1");
     }
+
+    #[test]
+    fn can_sync_directories() {
+        let base_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let src_dir =
+            tempdir_in(&base_path).expect("failed to create a temporary source directory.");
+        let dest_dir =
+            tempdir_in(&base_path).expect("failed to create a temporary destination directory.");
+        let (src, dest) = (src_dir.path(), dest_dir.path());
+        for (dir, files) in &[
+            (src, vec![("same.txt", "same"), ("changed.txt", "new"), ("posts/new.txt", "new")]),
+            (dest, vec![("same.txt", "same"), ("changed.txt", "old"), ("old/old.txt", "old")]),
+        ] {
+            for (path, content) in files {
+                create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+                File::create(dir.join(path)).unwrap().write_all(content.as_bytes()).unwrap();
+            }
+        }
+        filetime::set_file_mtime(dest.join("same.txt"), filetime::FileTime::from_unix_time(42, 0))
+            .unwrap();
+
+        let stats = sync_directory(src, dest).unwrap();
+        assert_eq!(stats, SyncStats { added: 1, changed: 1, removed: 1 });
+        assert_eq!(read_to_string(dest.join("changed.txt")).unwrap(), "new");
+        assert_eq!(read_to_string(dest.join("posts/new.txt")).unwrap(), "new");
+        assert!(!dest.join("old").exists());
+        // Unchanged files are not touched
+        assert_eq!(
+            filetime::FileTime::from_last_modification_time(
+                &metadata(dest.join("same.txt")).unwrap()
+            ),
+            filetime::FileTime::from_unix_time(42, 0)
+        );
+    }
+
+    #[test]
+    fn can_write_over_hard_links() {
+        let base_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let dir = tempdir_in(&base_path).expect("failed to create a temporary directory.");
+        let (original, link) = (dir.path().join("original.txt"), dir.path().join("link.txt"));
+        create_file(&original, "original").unwrap();
+        std::fs::hard_link(&original, &link).unwrap();
+
+        unlink_file(&link).unwrap();
+        create_file(&link, "new").unwrap();
+        assert_eq!(read_to_string(&original).unwrap(), "original");
+        assert_eq!(read_to_string(&link).unwrap(), "new");
+        // Nothing to remove is not an error
+        unlink_file(&dir.path().join("missing.txt")).unwrap();
+    }
 }
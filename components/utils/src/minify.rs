@@ -1,5 +1,11 @@
 use errors::{bail, Result};
+use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
 use minify_html::{minify, Cfg};
+use oxc_allocator::Allocator;
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use parcel_sourcemap::SourceMap;
 
 pub fn html(html: String) -> Result<String> {
     let mut cfg = Cfg::spec_compliant();
@@ -12,6 +18,143 @@ pub fn html(html: String) -> Result<String> {
     }
 }
 
+/// Minifies a stylesheet, also returning its source map as JSON if `source_map` is set.
+/// `filename` is only used in error messages and as the source in the source map
+pub fn css(css: &str, filename: &str, source_map: bool) -> Result<(String, Option<String>)> {
+    let options = ParserOptions { filename: filename.to_string(), ..ParserOptions::default() };
+    let mut stylesheet = match StyleSheet::parse(css, options) {
+        Ok(s) => s,
+        Err(err) => bail!("Failed to parse CSS of {}: {}", filename, err),
+    };
+    if let Err(err) = stylesheet.minify(MinifyOptions::default()) {
+        bail!("Failed to minify CSS of {}: {}", filename, err);
+    }
+
+    let mut map = SourceMap::new("/");
+    let printer_options = PrinterOptions {
+        minify: true,
+        source_map: if source_map { Some(&mut map) } else { None },
+        ..PrinterOptions::default()
+    };
+    let minified = match stylesheet.to_css(printer_options) {
+        Ok(res) => res.code,
+        Err(err) => bail!("Failed to minify CSS of {}: {}", filename, err),
+    };
+    if !source_map {
+        return Ok((minified, None));
+    }
+
+    // The original file isn't necessarily served so we embed it in the source map
+    if map.get_sources().is_empty() {
+        map.add_source(filename);
+    }
+    let json = map.set_source_content(0, css).and_then(|_| map.to_json(None));
+    match json {
+        Ok(json) => Ok((minified, Some(json))),
+        Err(err) => bail!("Failed to generate the source map of {}: {}", filename, err),
+    }
+}
+
+/// Minifies a script, `module` being whether it is an ES module, also returning its source map
+/// as JSON if `source_map` is set. Only the whitespace and the comments are removed so the
+/// script behaves exactly the same.
+/// `filename` is only used in error messages and as the source in the source map
+pub fn js(
+    js: &str,
+    filename: &str,
+    module: bool,
+    source_map: bool,
+) -> Result<(String, Option<String>)> {
+    let allocator = Allocator::default();
+    let source_type = if module { SourceType::mjs() } else { SourceType::cjs() };
+    let parsed = Parser::new(&allocator, js, source_type).parse();
+    if let Some(err) = parsed.errors.first() {
+        bail!("Failed to parse JavaScript of {}: {}", filename, err);
+    }
+
+    let options = CodegenOptions {
+        source_map_path: if source_map { Some(filename.into()) } else { None },
+        ..CodegenOptions::minify()
+    };
+    let minified = Codegen::new().with_options(options).build(&parsed.program);
+    // The original file isn't necessarily served so the source map embeds it
+    Ok((minified.code, minified.map.map(|map| map.to_json_string())))
+}
+
+/// Minifies the content of the inline `<style>` and/or `<script>` elements of an HTML page.
+/// Scripts that are neither classic scripts nor modules, eg JSON-LD, are left untouched
+pub fn inline(html: &str, minify_css: bool, minify_js: bool) -> Result<String> {
+    let lowercase = html.to_ascii_lowercase();
+    let mut res = String::with_capacity(html.len());
+    let mut pos = 0;
+
+    while let Some((start, tag)) = find_inline_element(&lowercase, pos) {
+        let content_start = match lowercase[start..].find('>') {
+            Some(i) => start + i + 1,
+            None => break,
+        };
+        let content_end = match lowercase[content_start..].find(&format!("</{}", tag)) {
+            Some(i) => content_start + i,
+            None => break,
+        };
+        res.push_str(&html[pos..content_start]);
+        pos = content_end;
+
+        let content = &html[content_start..content_end];
+        if content.trim().is_empty() {
+            res.push_str(content);
+            continue;
+        }
+        match tag {
+            "style" if minify_css => res.push_str(&css(content, "<style>", false)?.0),
+            "script" if minify_js => {
+                match script_type(&lowercase[start..content_start]).as_deref() {
+                    None | Some("") | Some("text/javascript") | Some("application/javascript") => {
+                        res.push_str(&js(content, "<script>", false, false)?.0)
+                    }
+                    Some("module") => res.push_str(&js(content, "<script>", true, false)?.0),
+                    _ => res.push_str(content),
+                }
+            }
+            _ => res.push_str(content),
+        }
+    }
+
+    res.push_str(&html[pos..]);
+    Ok(res)
+}
+
+/// Finds the next opening `<style>` or `<script>` tag, returning its position and name
+fn find_inline_element(lowercase: &str, from: usize) -> Option<(usize, &'static str)> {
+    let mut pos = from;
+    while let Some(i) = lowercase[pos..].find('<') {
+        let start = pos + i;
+        for tag in &["style", "script"] {
+            let rest = &lowercase[start + 1..];
+            if rest.starts_with(tag)
+                && rest[tag.len()..].starts_with(&['>', ' ', '\t', '\n', '\r', '/'][..])
+            {
+                return Some((start, tag));
+            }
+        }
+        pos = start + 1;
+    }
+    None
+}
+
+/// Returns the value of the `type` attribute of an opening tag, if there is one
+fn script_type(tag: &str) -> Option<String> {
+    let i = tag.find(" type=").or_else(|| tag.find("\ttype=")).or_else(|| tag.find("\ntype="))?;
+    let value = &tag[i + 6..];
+    let value = match value.chars().next() {
+        Some(quote @ '"') | Some(quote @ '\'') => {
+            value[1..].split(quote).next().unwrap_or_default()
+        }
+        _ => value.split(&[' ', '>', '/'][..]).next().unwrap_or_default(),
+    };
+    Some(value.trim().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +224,84 @@ mod tests {
         let res = html(input.to_owned()).unwrap();
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn can_minify_css() {
+        let input = ".a {\n  color: #ff0000;\n  margin: 0px 0px 0px 0px;\n}\n";
+        let (res, map) = css(input, "site.css", false).unwrap();
+        assert_eq!(res, ".a{color:red;margin:0}");
+        assert!(map.is_none());
+    }
+
+    #[test]
+    fn can_generate_css_source_maps() {
+        let input = ".a {\n  color: #ff0000;\n}\n";
+        let (res, map) = css(input, "site.css", true).unwrap();
+        assert_eq!(res, ".a{color:red}");
+        let map = map.unwrap();
+        assert!(map.contains(r#""sources":["site.css"]"#));
+        assert!(map.contains(r#""sourcesContent":[".a {\n  color: #ff0000;\n}\n"]"#));
+    }
+
+    #[test]
+    fn errors_on_invalid_css() {
+        let res = css("}}} .a {", "site.css", false);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn can_minify_js() {
+        let input = "function hello(name) {\n    // greet\n    const greeting = 'Hello ' + name;\n    console.log(greeting);\n}\n";
+        let (res, map) = js(input, "site.js", false, false).unwrap();
+        assert_eq!(res, "function hello(name){const greeting=`Hello `+name;console.log(greeting)}");
+        assert!(map.is_none());
+        assert!(js("function (", "site.js", false, false).is_err());
+    }
+
+    #[test]
+    fn can_minify_js_getters_and_setters() {
+        let input = "class B {\n  get size() { return 3; }\n  set size(v) { this.s = v; }\n}\nconst o = { get n() { return 1; } };\n";
+        let (res, _) = js(input, "site.js", false, false).unwrap();
+        assert!(res.contains("get size(){return 3}set size(v){this.s=v}"));
+        assert!(res.contains("{get n(){return 1}}"));
+    }
+
+    #[test]
+    fn can_minify_js_modules() {
+        let input = "import { a } from './a.js';\nexport const b = a + 1;\n";
+        let (res, _) = js(input, "site.mjs", true, false).unwrap();
+        assert!(res.contains("export const b=a+1"));
+    }
+
+    #[test]
+    fn can_generate_js_source_maps() {
+        let input = "var answer = 42;\n";
+        let (res, map) = js(input, "site.js", false, true).unwrap();
+        assert_eq!(res, "var answer=42;");
+        let map = map.unwrap();
+        assert!(map.contains(r#""sources":["site.js"]"#));
+        assert!(map.contains(r#""sourcesContent":["var answer = 42;\n"]"#));
+    }
+
+    #[test]
+    fn can_minify_inline_styles_and_scripts() {
+        let input = r#"<html><head><style>
+  .a { color: #ff0000; }
+</style>
+<script type="application/ld+json">{ "name": "zola" }</script>
+<SCRIPT>
+  var answer = 42;
+</SCRIPT>
+<script src="/app.js"></script>
+</head><body><pre>  <style>kept as is</pre></body></html>"#;
+        let res = inline(input, true, false).unwrap();
+        assert!(res.contains("<style>.a{color:red}</style>"));
+        assert!(res.contains("<SCRIPT>\n  var answer = 42;\n</SCRIPT>"));
+
+        let res = inline(input, false, true).unwrap();
+        assert!(res.contains("<style>\n  .a { color: #ff0000; }\n</style>"));
+        assert!(res.contains(r#"<script type="application/ld+json">{ "name": "zola" }</script>"#));
+        assert!(!res.contains("var answer = 42;\n"));
+        assert!(res.contains(r#"<script src="/app.js"></script>"#));
+    }
 }
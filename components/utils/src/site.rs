@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::hash::BuildHasher;
 use unicode_segmentation::UnicodeSegmentation;
 
-use errors::Result;
+use errors::{bail, Result};
 
 /// Get word count and estimated reading time
 pub fn get_reading_analytics(content: &str) -> (usize, usize) {
@@ -53,11 +53,58 @@ pub fn resolve_internal_link<S: BuildHasher>(
     }
 }
 
+/// The pages `[[wiki links]]` can point to: the lowercased slug and title of every page
+/// -> (language, internal path to the .md file) of the pages having it
+pub type WikiLinkTargets = HashMap<String, Vec<(String, String)>>;
+
+/// Resolves the target of a wiki link (the `Some page#anchor` of `[[Some page#anchor|label]]`)
+/// to the page of the given language having that slug or title, case-insensitively.
+/// Targets ending in `.md` are internal paths, like the `@/` links.
+/// Errors if no page or more than one page matches.
+pub fn resolve_wiki_link<S: BuildHasher>(
+    target: &str,
+    lang: &str,
+    wiki_links: &WikiLinkTargets,
+    permalinks: &HashMap<String, String, S>,
+) -> Result<ResolvedInternalLink> {
+    let (name, anchor) = match target.find('#') {
+        Some(idx) => (target[..idx].trim(), Some(&target[idx + 1..])),
+        None => (target.trim(), None),
+    };
+
+    let md_path = if name.ends_with(".md") {
+        name.trim_start_matches('/').to_owned()
+    } else {
+        let mut candidates: Vec<&str> = wiki_links
+            .get(&name.to_lowercase())
+            .map(|pages| pages.iter().filter(|(l, _)| l == lang).map(|(_, p)| p.as_str()).collect())
+            .unwrap_or_default();
+        candidates.sort_unstable();
+        candidates.dedup();
+        match candidates.as_slice() {
+            [] => bail!("Wiki link [[{}]] doesn't match the slug or title of any page", target),
+            [md_path] => md_path.to_string(),
+            _ => bail!(
+                "Wiki link [[{}]] is ambiguous, it matches several pages: {}",
+                target,
+                candidates.join(", ")
+            ),
+        }
+    };
+
+    let link = match anchor {
+        Some(anchor) => format!("@/{}#{}", md_path, anchor),
+        None => format!("@/{}", md_path),
+    };
+    resolve_internal_link(&link, permalinks)
+        .map_err(|_| format!("Wiki link [[{}]] doesn't match any page", target).into())
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
-    use super::{get_reading_analytics, resolve_internal_link};
+    use super::{get_reading_analytics, resolve_internal_link, resolve_wiki_link};
 
     #[test]
     fn can_resolve_valid_internal_link() {
@@ -104,6 +151,44 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn can_resolve_wiki_links_by_slug_title_and_path() {
+        let mut permalinks = HashMap::new();
+        permalinks.insert("posts/intro.md".to_string(), "https://vincent.is/intro/".to_string());
+        let mut wiki_links = HashMap::new();
+        let page = vec![("en".to_string(), "posts/intro.md".to_string())];
+        wiki_links.insert("intro".to_string(), page.clone());
+        wiki_links.insert("getting started".to_string(), page);
+
+        let res = resolve_wiki_link("intro", "en", &wiki_links, &permalinks).unwrap();
+        assert_eq!(res.permalink, "https://vincent.is/intro/");
+        let res =
+            resolve_wiki_link("Getting Started#setup", "en", &wiki_links, &permalinks).unwrap();
+        assert_eq!(res.permalink, "https://vincent.is/intro/#setup");
+        assert_eq!(res.md_path, "posts/intro.md".to_string());
+        assert_eq!(res.anchor, Some("setup".to_string()));
+        let res = resolve_wiki_link("posts/intro.md", "en", &wiki_links, &permalinks).unwrap();
+        assert_eq!(res.permalink, "https://vincent.is/intro/");
+    }
+
+    #[test]
+    fn errors_on_unresolved_or_ambiguous_wiki_links() {
+        let mut permalinks = HashMap::new();
+        permalinks.insert("a.md".to_string(), "https://vincent.is/a/".to_string());
+        permalinks.insert("b.md".to_string(), "https://vincent.is/b/".to_string());
+        let mut wiki_links = HashMap::new();
+        wiki_links.insert(
+            "notes".to_string(),
+            vec![("en".to_string(), "a.md".to_string()), ("en".to_string(), "b.md".to_string())],
+        );
+
+        let err = resolve_wiki_link("Notes", "en", &wiki_links, &permalinks).unwrap_err();
+        assert!(format!("{}", err).contains("ambiguous, it matches several pages: a.md, b.md"));
+        assert!(resolve_wiki_link("Notes", "fr", &wiki_links, &permalinks).is_err());
+        assert!(resolve_wiki_link("missing", "en", &wiki_links, &permalinks).is_err());
+        assert!(resolve_wiki_link("missing.md", "en", &wiki_links, &permalinks).is_err());
+    }
+
     #[test]
     fn reading_analytics_empty_text() {
         let (word_count, reading_time) = get_reading_analytics("  ");
@@ -51,6 +51,14 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("drafts")
                         .takes_value(false)
                         .help("Include drafts when loading the site"),
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .takes_value(false)
+                        .help("Only use the cached or fixture responses for the remote data of `load_data`"),
+                    Arg::with_name("sync")
+                        .long("sync")
+                        .takes_value(false)
+                        .help("Only write the files that changed and remove the stale ones instead of deleting the output directory"),
                 ]),
             SubCommand::with_name("serve")
                 .about("Serve the site. Rebuild and reload on change automatically")
@@ -89,6 +97,10 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("fast")
                         .takes_value(false)
                         .help("Only rebuild the minimum on change - useful when working on a specific page/section"),
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .takes_value(false)
+                        .help("Only use the cached or fixture responses for the remote data of `load_data`"),
                 ]),
             SubCommand::with_name("check")
                 .about("Try building the project without rendering it. Checks links")
@@ -97,6 +109,10 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("drafts")
                         .takes_value(false)
                         .help("Include drafts when loading the site"),
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .takes_value(false)
+                        .help("Only use the cached or fixture responses for the remote data of `load_data`"),
                 ])
         ])
 }
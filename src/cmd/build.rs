@@ -14,6 +14,8 @@ pub fn build(
     base_url: Option<&str>,
     output_dir: Option<&Path>,
     include_drafts: bool,
+    offline: bool,
+    sync: bool,
 ) -> Result<()> {
     let mut site = Site::new(root_dir, config_file)?;
     if let Some(output_dir) = output_dir {
@@ -46,8 +48,23 @@ pub fn build(
     if include_drafts {
         site.include_drafts();
     }
+    if offline {
+        site.enable_offline_mode();
+    }
+    if sync {
+        site.enable_sync_mode();
+    }
     site.load()?;
     console::notify_site_size(&site);
     console::warn_about_ignored_pages(&site);
-    site.build()
+    if sync {
+        let stats = site.build_and_sync()?;
+        console::info(&format!(
+            "-> Synced output: {} added, {} changed, {} removed",
+            stats.added, stats.changed, stats.removed
+        ));
+        Ok(())
+    } else {
+        site.build()
+    }
 }
@@ -21,6 +21,7 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+use std::collections::HashSet;
 use std::fs::{read_dir, remove_dir_all};
 use std::net::{SocketAddrV4, TcpListener};
 use std::path::{Path, PathBuf};
@@ -35,13 +36,15 @@ use hyper::{Body, Method, Request, Response, StatusCode};
 use mime_guess::from_path as mimetype_from_path;
 
 use chrono::prelude::*;
-use notify::{watcher, RecursiveMode, Watcher};
+use notify::{watcher, RecommendedWatcher, RecursiveMode, Watcher};
 use ws::{Message, Sender, WebSocket};
 
+use config::Compression;
 use errors::{Error as ZolaError, Result};
 use globset::GlobSet;
 use pathdiff::diff_paths;
 use relative_path::{RelativePath, RelativePathBuf};
+use site::compress::{compress, Encoding};
 use site::sass::compile_sass;
 use site::{Site, SITE_CONTENT};
 use utils::fs::copy_file;
@@ -57,6 +60,8 @@ enum ChangeKind {
     StaticFiles,
     Sass,
     Config,
+    Data,
+    IncludedFile,
 }
 
 #[derive(Debug, PartialEq)]
@@ -72,7 +77,11 @@ static NOT_FOUND_TEXT: &[u8] = b"Not Found";
 // This is dist/livereload.min.js from the LiveReload.js v3.2.4 release
 const LIVE_RELOAD: &str = include_str!("livereload.js");
 
-async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Response<Body>> {
+async fn handle_request(
+    req: Request<Body>,
+    mut root: PathBuf,
+    compression: Compression,
+) -> Result<Response<Body>> {
     let original_root = root.clone();
     let mut path = RelativePathBuf::new();
     // https://zola.discourse.group/t/percent-encoding-for-slugs/736
@@ -95,7 +104,7 @@ async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Respons
     }
 
     if let Some(content) = SITE_CONTENT.read().unwrap().get(&path) {
-        return Ok(in_memory_content(&path, content));
+        return Ok(in_memory_content(&req, &compression, &path, content));
     }
 
     // Handle only `GET`/`HEAD` requests
@@ -134,15 +143,72 @@ async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Respons
         Ok(contents) => contents,
     };
 
-    Ok(Response::builder()
+    let extension = root.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let (contents, encoding) = compress_body(&req, &compression, extension, contents);
+    let mut builder = Response::builder()
         .status(StatusCode::OK)
         .header(
             header::CONTENT_TYPE,
             mimetype_from_path(&root).first_or_octet_stream().essence_str(),
         )
-        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-        .body(Body::from(contents))
-        .unwrap())
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+    if let Some(encoding) = encoding {
+        builder = builder
+            .header(header::CONTENT_ENCODING, encoding.name())
+            .header(header::VARY, "Accept-Encoding");
+    }
+
+    Ok(builder.body(Body::from(contents)).unwrap())
+}
+
+/// Returns the encoding to use for a response, if `compress_output` is enabled: the one a
+/// server serving the compressed copies of the files would pick, brotli being preferred
+fn preferred_encoding(accept_encoding: &str, compression: &Compression) -> Option<Encoding> {
+    let mut accepted = Vec::new();
+    for value in accept_encoding.split(',') {
+        let mut parts = value.split(';');
+        let name = parts.next().unwrap_or_default().trim().to_lowercase();
+        // `gzip;q=0` means that gzip is not acceptable
+        let refused = parts
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .any(|q| matches!(q.parse::<f32>(), Ok(q) if q <= 0.0));
+        if !refused {
+            accepted.push(name);
+        }
+    }
+    let accepts = |name: &str| accepted.iter().any(|a| a == name || a == "*");
+
+    if compression.brotli && accepts(Encoding::Brotli.name()) {
+        Some(Encoding::Brotli)
+    } else if compression.gzip && accepts(Encoding::Gzip.name()) {
+        Some(Encoding::Gzip)
+    } else {
+        None
+    }
+}
+
+/// Compresses the body of a response like `zola build` would compress the file it comes from
+fn compress_body(
+    req: &Request<Body>,
+    compression: &Compression,
+    extension: &str,
+    body: Vec<u8>,
+) -> (Vec<u8>, Option<Encoding>) {
+    if !compression.should_compress(extension, body.len()) {
+        return (body, None);
+    }
+    let accept_encoding = match req.headers().get(header::ACCEPT_ENCODING) {
+        Some(value) => value.to_str().unwrap_or_default(),
+        None => return (body, None),
+    };
+
+    match preferred_encoding(accept_encoding, compression) {
+        Some(encoding) => match compress(&body, encoding, false) {
+            Ok(compressed) => (compressed, Some(encoding)),
+            Err(_) => (body, None),
+        },
+        None => (body, None),
+    }
 }
 
 fn livereload_js() -> Response<Body> {
@@ -153,20 +219,31 @@ fn livereload_js() -> Response<Body> {
         .expect("Could not build livereload.js response")
 }
 
-fn in_memory_content(path: &RelativePathBuf, content: &str) -> Response<Body> {
-    let content_type = match path.extension() {
+fn in_memory_content(
+    req: &Request<Body>,
+    compression: &Compression,
+    path: &RelativePathBuf,
+    content: &str,
+) -> Response<Body> {
+    let (extension, content_type) = match path.extension() {
         Some(ext) => match ext {
-            "xml" => "text/xml",
-            "json" => "application/json",
-            _ => "text/html",
+            "xml" => ("xml", "text/xml"),
+            "json" => ("json", "application/json"),
+            _ => ("html", "text/html"),
         },
-        None => "text/html",
+        None => ("html", "text/html"),
     };
-    Response::builder()
-        .header(header::CONTENT_TYPE, content_type)
-        .status(StatusCode::OK)
-        .body(content.to_owned().into())
-        .expect("Could not build HTML response")
+    let (content, encoding) =
+        compress_body(req, compression, extension, content.as_bytes().to_vec());
+    let mut builder =
+        Response::builder().header(header::CONTENT_TYPE, content_type).status(StatusCode::OK);
+    if let Some(encoding) = encoding {
+        builder = builder
+            .header(header::CONTENT_ENCODING, encoding.name())
+            .header(header::VARY, "Accept-Encoding");
+    }
+
+    builder.body(content.into()).expect("Could not build HTML response")
 }
 
 fn method_not_allowed() -> Response<Body> {
@@ -238,6 +315,7 @@ fn create_new_site(
     base_url: &str,
     config_file: &Path,
     include_drafts: bool,
+    offline: bool,
     ws_port: Option<u16>,
 ) -> Result<(Site, String)> {
     SITE_CONTENT.write().unwrap().clear();
@@ -261,6 +339,9 @@ fn create_new_site(
     if include_drafts {
         site.include_drafts();
     }
+    if offline {
+        site.enable_offline_mode();
+    }
     site.load()?;
     if let Some(p) = ws_port {
         site.enable_live_reload_with_port(p);
@@ -284,6 +365,7 @@ pub fn serve(
     open: bool,
     include_drafts: bool,
     fast_rebuild: bool,
+    offline: bool,
 ) -> Result<()> {
     let start = Instant::now();
     let (mut site, address) = create_new_site(
@@ -294,6 +376,7 @@ pub fn serve(
         base_url,
         config_file,
         include_drafts,
+        offline,
         None,
     )?;
     console::report_elapsed_time(start);
@@ -316,6 +399,7 @@ pub fn serve(
     let watch_this = vec![
         (config_path_rel.to_str().unwrap_or("config.toml"), WatchMode::Required),
         ("content", WatchMode::Required),
+        ("data", WatchMode::Optional),
         ("sass", WatchMode::Condition(site.config.compile_sass)),
         ("static", WatchMode::Optional),
         ("templates", WatchMode::Optional),
@@ -346,6 +430,9 @@ pub fn serve(
             watchers.push(entry.to_string());
         }
     }
+    // The files included in code blocks, if they are not in one of the folders above
+    let mut included_files = HashSet::new();
+    watch_included_files(&mut watcher, &site, &watchers, &mut included_files);
 
     let ws_port = site.live_reload;
     let ws_address = format!("{}:{}", interface, ws_port.unwrap());
@@ -354,6 +441,7 @@ pub fn serve(
     // output path is going to need to be moved later on, so clone it for the
     // http closure to avoid contention.
     let static_root = output_path.clone();
+    let compression = site.config.compress_output.clone();
     let broadcaster = {
         thread::spawn(move || {
             let addr = address.parse().unwrap();
@@ -366,10 +454,11 @@ pub fn serve(
             rt.block_on(async {
                 let make_service = make_service_fn(move |_| {
                     let static_root = static_root.clone();
+                    let compression = compression.clone();
 
                     async {
                         Ok::<_, hyper::Error>(service_fn(move |req| {
-                            handle_request(req, static_root.clone())
+                            handle_request(req, static_root.clone(), compression.clone())
                         }))
                     }
                 });
@@ -443,7 +532,7 @@ pub fn serve(
         console::info(&msg);
         rebuild_done_handling(
             &broadcaster,
-            compile_sass(&site.base_path, &site.output_path),
+            compile_sass(&site.base_path, &site.output_path, &site.config).map(|_| ()),
             &partial_path.to_string_lossy(),
         );
     };
@@ -488,6 +577,7 @@ pub fn serve(
         base_url,
         config_file,
         include_drafts,
+        offline,
         ws_port,
     ) {
         Ok((s, _)) => {
@@ -527,7 +617,11 @@ pub fn serve(
                         );
 
                         let start = Instant::now();
-                        match detect_change_kind(root_dir, &path, &config_path) {
+                        // Files that stopped being included can still be watched
+                        let mut all_included_files = site.included_files();
+                        all_included_files.extend(included_files.iter().cloned());
+                        match detect_change_kind(root_dir, &path, &config_path, &all_included_files)
+                        {
                             (ChangeKind::Content, _) => {
                                 console::info(&format!("-> Content changed {}", path.display()));
 
@@ -602,11 +696,30 @@ pub fn serve(
                             (ChangeKind::Config, _) => {
                                 console::info("-> Config changed. The browser needs to be refreshed to make the changes visible.");
 
+                                if let Some(s) = recreate_site() {
+                                    site = s;
+                                }
+                            }
+                            (ChangeKind::Data, _) => {
+                                console::info(&format!("-> Data changed {}", path.display()));
+
+                                if let Some(s) = recreate_site() {
+                                    site = s;
+                                }
+                            }
+                            (ChangeKind::IncludedFile, _) => {
+                                console::info(&format!(
+                                    "-> File included in a code block changed {}",
+                                    path.display()
+                                ));
+
                                 if let Some(s) = recreate_site() {
                                     site = s;
                                 }
                             }
                         };
+                        // Pages might have started including new files
+                        watch_included_files(&mut watcher, &site, &watchers, &mut included_files);
                         console::report_elapsed_time(start);
                     }
                     _ => {}
@@ -653,11 +766,20 @@ fn is_temp_file(path: &Path) -> bool {
 
 /// Detect what changed from the given path so we have an idea what needs
 /// to be reloaded
-fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKind, PathBuf) {
+fn detect_change_kind(
+    pwd: &Path,
+    path: &Path,
+    config_path: &Path,
+    included_files: &HashSet<PathBuf>,
+) -> (ChangeKind, PathBuf) {
     let mut partial_path = PathBuf::from("/");
     partial_path.push(path.strip_prefix(pwd).unwrap_or(path));
 
-    let change_kind = if partial_path.starts_with("/templates") {
+    // A file included in code blocks can be in any folder, eg `static`, and the pages
+    // including it need to be rendered again on top of what that folder needs
+    let change_kind = if path != config_path && included_files.contains(path) {
+        ChangeKind::IncludedFile
+    } else if partial_path.starts_with("/templates") {
         ChangeKind::Templates
     } else if partial_path.starts_with("/themes") {
         ChangeKind::Themes
@@ -667,6 +789,8 @@ fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKin
         ChangeKind::StaticFiles
     } else if partial_path.starts_with("/sass") {
         ChangeKind::Sass
+    } else if partial_path.starts_with("/data") {
+        ChangeKind::Data
     } else if path == config_path {
         ChangeKind::Config
     } else {
@@ -676,6 +800,34 @@ fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKin
     (change_kind, partial_path)
 }
 
+/// Watches the files included in code blocks that are not already watched, either
+/// because they are in one of the watched folders or because we saw them in a previous build.
+/// `detect_change_kind` recognizes them in the watched folders as well
+fn watch_included_files(
+    watcher: &mut RecommendedWatcher,
+    site: &Site,
+    watched_dirs: &[String],
+    included_files: &mut HashSet<PathBuf>,
+) {
+    for path in site.included_files() {
+        if included_files.contains(&path)
+            || watched_dirs.iter().any(|dir| path.starts_with(site.base_path.join(dir)))
+        {
+            continue;
+        }
+        match watcher.watch(&path, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                included_files.insert(path);
+            }
+            Err(e) => console::warn(&format!(
+                "Can't watch included file `{}` for changes: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+}
+
 /// Check if the directory at path contains any file
 fn is_folder_empty(dir: &Path) -> bool {
     // Can panic if we don't have the rights I guess?
@@ -685,9 +837,13 @@ fn is_folder_empty(dir: &Path) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::path::{Path, PathBuf};
 
-    use super::{detect_change_kind, is_temp_file, ChangeKind};
+    use config::Compression;
+    use site::compress::Encoding;
+
+    use super::{detect_change_kind, is_temp_file, preferred_encoding, ChangeKind};
 
     #[test]
     fn can_recognize_temp_files() {
@@ -747,6 +903,24 @@ mod tests {
                 Path::new("/home/vincent/site/config.toml"),
                 Path::new("/home/vincent/site/config.toml"),
             ),
+            (
+                (ChangeKind::Data, PathBuf::from("/data/team.toml")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/data/team.toml"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
+            (
+                (ChangeKind::IncludedFile, PathBuf::from("/examples/demo.rs")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/examples/demo.rs"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
+            (
+                (ChangeKind::IncludedFile, PathBuf::from("/static/demo.js")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/static/demo.js"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
             (
                 (ChangeKind::Config, PathBuf::from("/config.staging.toml")),
                 Path::new("/home/vincent/site"),
@@ -754,9 +928,15 @@ mod tests {
                 Path::new("/home/vincent/site/config.staging.toml"),
             ),
         ];
+        let included_files: HashSet<_> = vec![
+            PathBuf::from("/home/vincent/site/examples/demo.rs"),
+            PathBuf::from("/home/vincent/site/static/demo.js"),
+        ]
+        .into_iter()
+        .collect();
 
         for (expected, pwd, path, config_filename) in test_cases {
-            assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+            assert_eq!(expected, detect_change_kind(pwd, path, config_filename, &included_files));
         }
     }
 
@@ -767,7 +947,7 @@ mod tests {
         let pwd = Path::new(r#"C:\\Users\johan\site"#);
         let path = Path::new(r#"C:\\Users\johan\site\templates\hello.html"#);
         let config_filename = Path::new(r#"C:\\Users\johan\site\config.toml"#);
-        assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+        assert_eq!(expected, detect_change_kind(pwd, path, config_filename, &HashSet::new()));
     }
 
     #[test]
@@ -776,6 +956,27 @@ mod tests {
         let pwd = Path::new("/home/johan/site");
         let path = Path::new("templates/hello.html");
         let config_filename = Path::new("config.toml");
-        assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+        assert_eq!(expected, detect_change_kind(pwd, path, config_filename, &HashSet::new()));
+    }
+
+    #[test]
+    fn can_pick_the_preferred_encoding() {
+        let compression = Compression { enabled: true, ..Compression::default() };
+        let test_cases = vec![
+            ("gzip, deflate, br", Some(Encoding::Brotli)),
+            ("gzip, deflate", Some(Encoding::Gzip)),
+            ("br;q=0, gzip;q=0.5", Some(Encoding::Gzip)),
+            ("BR", Some(Encoding::Brotli)),
+            ("*", Some(Encoding::Brotli)),
+            ("identity", None),
+            ("gzip;q=0", None),
+            ("", None),
+        ];
+        for (accept_encoding, expected) in test_cases {
+            assert_eq!(preferred_encoding(accept_encoding, &compression), expected);
+        }
+
+        let compression = Compression { enabled: true, brotli: false, ..Compression::default() };
+        assert_eq!(preferred_encoding("gzip, br", &compression), Some(Encoding::Gzip));
     }
 }
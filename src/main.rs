@@ -46,6 +46,8 @@ fn main() {
                 matches.value_of("base_url"),
                 output_dir,
                 matches.is_present("drafts"),
+                matches.is_present("offline"),
+                matches.is_present("sync"),
             ) {
                 Ok(()) => console::report_elapsed_time(start),
                 Err(e) => {
@@ -66,6 +68,7 @@ fn main() {
             let open = matches.is_present("open");
             let include_drafts = matches.is_present("drafts");
             let fast = matches.is_present("fast");
+            let offline = matches.is_present("offline");
 
             // Default one
             if port != 1111 && !port_is_available(port) {
@@ -94,6 +97,7 @@ fn main() {
                 open,
                 include_drafts,
                 fast,
+                offline,
             ) {
                 Ok(()) => (),
                 Err(e) => {
@@ -111,6 +115,7 @@ fn main() {
                 matches.value_of("base_path"),
                 matches.value_of("base_url"),
                 matches.is_present("drafts"),
+                matches.is_present("offline"),
             ) {
                 Ok(()) => console::report_elapsed_time(start),
                 Err(e) => {
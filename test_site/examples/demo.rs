@@ -0,0 +1,9 @@
+use std::collections::HashMap;
+
+// ANCHOR: main
+fn main() {
+    let mut map = HashMap::new();
+    map.insert("hello", "world");
+    println!("{:?}", map);
+}
+// ANCHOR_END: main